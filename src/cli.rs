@@ -1,14 +1,24 @@
-use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    parser::ValueSource,
+    value_parser, Arg, ArgAction, ArgMatches, Command, ValueEnum,
+};
 use indicatif::{ProgressBar, ProgressStyle};
 use png::ColorType;
 
 use std::{env, path::PathBuf};
 
 use libmosh::{
-    ops::{read_file, write_file},
-    MoshCore,
+    err::MoshError,
+    fx::{MoshDither, MoshEdge, MoshSortKey},
+    ops::{read_file, write_file, write_indexed_file},
+    IndexedMode, MoshBlend, MoshColorSpace, MoshCore, MoshFilterType, MoshOptions, MoshOrientation,
+    MoshPlan, MoshShiftUnit, MoshSize, MoshSkewDirection, MoshSortMode, MoshWaveShape,
 };
 
+#[cfg(feature = "serde")]
+use libmosh::recipe::{read_recipe, write_recipe, MoshRecipe};
+
 // Logo
 const BANNER: &str = "\u{250C}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\
     \u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\
@@ -53,6 +63,16 @@ const SPINNER_2: [&str; 7] = [
     "\u{25B0}\u{25B0}\u{25B0}\u{25B0}\u{25B0}",
 ];
 
+struct Args {
+    input: PathBuf,
+    output: String,
+    container: MoshCore,
+    batch: u8,
+    plan: Option<MoshPlan>,
+    #[cfg(feature = "serde")]
+    save_recipe: Option<PathBuf>,
+}
+
 // Checks for TTY
 fn display_var() -> bool {
     env::var("DISPLAY").is_ok()
@@ -68,6 +88,49 @@ fn color_type(container: &MoshCore) -> &str {
     }
 }
 
+// Chunk sizes, `auto` keeps the default
+fn size(value: &str) -> Result<Option<MoshSize>, MoshError> {
+    match value {
+        "auto" => Ok(None),
+        _ => value.parse().map(Some),
+    }
+}
+
+fn offsets(value: &str) -> Result<Vec<(i32, i32)>, MoshError> {
+    if value == "auto" {
+        return Ok(Vec::new());
+    }
+
+    value
+        .split(',')
+        .map(|offset| {
+            offset
+                .split_once(':')
+                .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+                .ok_or_else(|| MoshError::InvalidParameters {
+                    field: "offsets",
+                    range: String::from("X:Y per channel, comma-separated"),
+                })
+        })
+        .collect()
+}
+
+fn bits(value: &str) -> Result<Vec<u8>, MoshError> {
+    value
+        .split(',')
+        .map(|bits| {
+            bits.trim()
+                .parse()
+                .ok()
+                .filter(|bits| (1..=16).contains(bits))
+                .ok_or_else(|| MoshError::InvalidParameters {
+                    field: "bits",
+                    range: String::from("1..=16 per channel, comma-separated"),
+                })
+        })
+        .collect()
+}
+
 fn arg_matches() -> (ArgMatches, MoshCore) {
     let container = MoshCore::new();
     let matches = Command::new(env!("CARGO_PKG_NAME"))
@@ -106,6 +169,48 @@ fn arg_matches() -> (ArgMatches, MoshCore) {
                 .value_parser(value_parser!(u16))
                 .default_value(container.options.max_rate.to_string()),
         )
+        .arg(
+            Arg::new("minchunkheight")
+                .long("min-chunk-height")
+                .value_name("HEIGHT")
+                .help("Minimum chunk height")
+                .long_help("Minimum chunk height, in pixels or percent of the image height")
+                .value_parser(size)
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::new("maxchunkheight")
+                .long("max-chunk-height")
+                .value_name("HEIGHT")
+                .help("Maximum chunk height")
+                .long_help("Maximum chunk height, in pixels or percent of the image height")
+                .value_parser(size)
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::new("minchunkwidth")
+                .long("min-chunk-width")
+                .value_name("WIDTH")
+                .help("Minimum chunk width")
+                .long_help(
+                    "Minimum chunk width, in pixels or percent of the image width. \
+                    Chunks span whole lines unless a width is set",
+                )
+                .value_parser(size)
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::new("maxchunkwidth")
+                .long("max-chunk-width")
+                .value_name("WIDTH")
+                .help("Maximum chunk width")
+                .long_help(
+                    "Maximum chunk width, in pixels or percent of the image width. \
+                    Chunks span whole lines unless a width is set",
+                )
+                .value_parser(size)
+                .default_value("auto"),
+        )
         .arg(
             Arg::new("pixelation")
                 .short('p')
@@ -160,6 +265,382 @@ fn arg_matches() -> (ArgMatches, MoshCore) {
                 .value_parser(value_parser!(f64))
                 .default_value(container.options.channel_shift.to_string()),
         )
+        .arg(
+            Arg::new("shiftunit")
+                .short('u')
+                .long("shift-unit")
+                .value_name("UNIT")
+                .help("Line and channel shift granularity")
+                .long_help(
+                    "Shift lines and channels by any amount of samples, \
+                    or by whole pixels to keep colors intact",
+                )
+                .value_parser(value_parser!(MoshShiftUnit))
+                .default_value("sample"),
+        )
+        .arg(
+            Arg::new("colorspace")
+                .long("color-space")
+                .value_name("SPACE")
+                .help("Channel shift and swap color space")
+                .long_help(
+                    "Shift and swap luma and chroma (ycbcr) or hue, saturation \
+                    and value (hsv) planes of RGB images",
+                )
+                .value_parser(value_parser!(MoshColorSpace))
+                .default_value("rgb"),
+        )
+        .arg(
+            Arg::new("bitflip")
+                .long("bit-flip")
+                .value_name("BIT_FLIP")
+                .help("Bit flip rate")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.bit_flip.to_string()),
+        )
+        .arg(
+            Arg::new("bitflipintensity")
+                .long("bit-flip-intensity")
+                .value_name("N")
+                .help("Bits flipped per chunk")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.bit_flip_intensity.to_string()),
+        )
+        .arg(
+            Arg::new("bytereplace")
+                .long("byte-replace")
+                .value_name("BYTE_REPLACE")
+                .help("Byte replacement rate")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.byte_replace.to_string()),
+        )
+        .arg(
+            Arg::new("bytereplaceintensity")
+                .long("byte-replace-intensity")
+                .value_name("N")
+                .help("Bytes replaced per chunk")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.byte_replace_intensity.to_string()),
+        )
+        .arg(
+            Arg::new("byteinsert")
+                .long("byte-insert")
+                .value_name("BYTE_INSERT")
+                .help("Byte insertion rate")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.byte_insert.to_string()),
+        )
+        .arg(
+            Arg::new("byteinsertintensity")
+                .long("byte-insert-intensity")
+                .value_name("N")
+                .help("Bytes inserted per chunk")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.byte_insert_intensity.to_string()),
+        )
+        .arg(
+            Arg::new("bytedelete")
+                .long("byte-delete")
+                .value_name("BYTE_DELETE")
+                .help("Byte deletion rate")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.byte_delete.to_string()),
+        )
+        .arg(
+            Arg::new("bytedeleteintensity")
+                .long("byte-delete-intensity")
+                .value_name("N")
+                .help("Bytes deleted per chunk")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.byte_delete_intensity.to_string()),
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .value_name("SORT")
+                .help("Pixel sort rate")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.sort.to_string()),
+        )
+        .arg(
+            Arg::new("sortkey")
+                .long("sort-key")
+                .value_name("KEY")
+                .help("Pixel sort key")
+                .long_help("Sort pixels by luminance, hue or a single channel (by index)")
+                .value_parser(
+                    PossibleValuesParser::new(["luminance", "hue", "0", "1", "2", "3"]).map(
+                        |key| match key.as_str() {
+                            "luminance" => MoshSortKey::Luminance,
+                            "hue" => MoshSortKey::Hue,
+                            channel => MoshSortKey::Channel(channel.parse().unwrap()),
+                        },
+                    ),
+                )
+                .default_value("luminance"),
+        )
+        .arg(
+            Arg::new("sortruns")
+                .long("sort-runs")
+                .value_name("RUNS")
+                .help("Pixel sort runs")
+                .long_help(
+                    "Sort runs of pixels with keys between the thresholds, \
+                    or runs of random lengths",
+                )
+                .value_parser(value_parser!(MoshSortMode))
+                .default_value("threshold"),
+        )
+        .arg(
+            Arg::new("sortmin")
+                .long("sort-min")
+                .value_name("MIN")
+                .help("Lowest sorted key")
+                .value_parser(value_parser!(u8))
+                .default_value(container.options.sort_min.to_string()),
+        )
+        .arg(
+            Arg::new("sortmax")
+                .long("sort-max")
+                .value_name("MAX")
+                .help("Highest sorted key")
+                .value_parser(value_parser!(u8))
+                .default_value(container.options.sort_max.to_string()),
+        )
+        .arg(
+            Arg::new("sortinterval")
+                .long("sort-interval")
+                .value_name("LENGTH")
+                .help("Longest sorted interval")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.sort_interval.to_string()),
+        )
+        .arg(
+            Arg::new("rgbsplit")
+                .long("rgb-split")
+                .value_name("RGB_SPLIT")
+                .help("RGB split rate")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.rgb_split.to_string()),
+        )
+        .arg(
+            Arg::new("rgbsplitrange")
+                .long("rgb-split-range")
+                .value_name("PIXELS")
+                .help("Largest RGB split offset")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.rgb_split_range.to_string()),
+        )
+        .arg(
+            Arg::new("rgbsplitoffsets")
+                .long("rgb-split-offsets")
+                .value_name("OFFSETS")
+                .help("Fixed RGB split offsets")
+                .long_help(
+                    "Fixed offsets per color channel, e.g. -4:0,0:0,4:0, \
+                    sampled from the range when auto",
+                )
+                .allow_hyphen_values(true)
+                .value_parser(offsets)
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::new("rgbsplitedge")
+                .long("rgb-split-edge")
+                .value_name("EDGE")
+                .help("RGB split edge handling")
+                .value_parser(value_parser!(MoshEdge))
+                .default_value("clamp"),
+        )
+        .arg(
+            Arg::new("wave")
+                .long("wave")
+                .value_name("WAVE")
+                .help("Wave displacement rate")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.wave.to_string()),
+        )
+        .arg(
+            Arg::new("waveshape")
+                .long("wave-shape")
+                .value_name("SHAPE")
+                .help("Wave shape")
+                .value_parser(value_parser!(MoshWaveShape))
+                .default_value("sine"),
+        )
+        .arg(
+            Arg::new("waveamplitude")
+                .long("wave-amplitude")
+                .value_name("PIXELS")
+                .help("Largest wave offset")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.wave_amplitude.to_string()),
+        )
+        .arg(
+            Arg::new("waveperiod")
+                .long("wave-period")
+                .value_name("LINES")
+                .help("Wave length")
+                .long_help("Wave length (inverse frequency), in lines")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.wave_period.to_string()),
+        )
+        .arg(
+            Arg::new("wavephase")
+                .long("wave-phase")
+                .value_name("LINES")
+                .help("Wave phase")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.wave_phase.to_string()),
+        )
+        .arg(
+            Arg::new("blocks")
+                .long("blocks")
+                .value_name("BLOCKS")
+                .help("Macroblock mutation rate")
+                .long_help("Displace, duplicate or swap square blocks of chunks")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.blocks.to_string()),
+        )
+        .arg(
+            Arg::new("blocksize")
+                .long("block-size")
+                .value_name("PIXELS")
+                .help("Macroblock size")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.block_size.to_string()),
+        )
+        .arg(
+            Arg::new("blockdisplacement")
+                .long("block-displacement")
+                .value_name("CHANCE")
+                .help("Mutated macroblock rate")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.block_displacement.to_string()),
+        )
+        .arg(
+            Arg::new("smear")
+                .long("smear")
+                .value_name("SMEAR")
+                .help("Line smear rate")
+                .long_help("Copy a line (or a pixel) over the following ones")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.smear.to_string()),
+        )
+        .arg(
+            Arg::new("smearlength")
+                .long("smear-length")
+                .value_name("LENGTH")
+                .help("Longest smear streak")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.smear_length.to_string()),
+        )
+        .arg(
+            Arg::new("smeardirection")
+                .long("smear-direction")
+                .value_name("DIRECTION")
+                .help("Smear streak direction")
+                .value_parser(value_parser!(MoshOrientation))
+                .default_value("vertical"),
+        )
+        .arg(
+            Arg::new("smearpartial")
+                .long("smear-partial")
+                .help("Smear a random span only")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("chunkcopy")
+                .long("chunk-copy")
+                .value_name("COPY")
+                .help("Chunk copy rate")
+                .long_help("Copy a chunk to another line")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.chunk_copy.to_string()),
+        )
+        .arg(
+            Arg::new("chunkswap")
+                .long("chunk-swap")
+                .value_name("SWAP")
+                .help("Chunk swap rate")
+                .long_help("Swap a chunk with another region of the same size")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.chunk_swap.to_string()),
+        )
+        .arg(
+            Arg::new("chunktile")
+                .long("chunk-tile")
+                .value_name("TILE")
+                .help("Chunk tiling rate")
+                .long_help("Repeat a chunk below itself")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.chunk_tile.to_string()),
+        )
+        .arg(
+            Arg::new("chunktilecount")
+                .long("chunk-tile-count")
+                .value_name("COUNT")
+                .help("Most chunk repeats")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.chunk_tile_count.to_string()),
+        )
+        .arg(
+            Arg::new("crush")
+                .long("crush")
+                .value_name("CRUSH")
+                .help("Bitcrush rate")
+                .long_help("Reduce bits per channel of a chunk")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.crush.to_string()),
+        )
+        .arg(
+            Arg::new("crushbits")
+                .long("crush-bits")
+                .value_name("BITS")
+                .help("Kept bits per channel")
+                .long_help("Kept bits per channel, e.g. 5,6,5, the last value applies to the rest")
+                .value_parser(bits)
+                .default_value("4"),
+        )
+        .arg(
+            Arg::new("crushdither")
+                .long("crush-dither")
+                .value_name("DITHER")
+                .help("Bitcrush dithering")
+                .value_parser(value_parser!(MoshDither))
+                .default_value("none"),
+        )
+        .arg(
+            Arg::new("crushglobal")
+                .long("crush-global")
+                .help("Crush the whole image")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("skew")
+                .long("skew")
+                .value_name("SKEW")
+                .help("Stride skew rate")
+                .long_help("Read a chunk with a wrong line width, shearing it diagonally")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.skew.to_string()),
+        )
+        .arg(
+            Arg::new("skewrange")
+                .long("skew-range")
+                .value_name("RANGE")
+                .help("Largest stride error")
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.skew_range.to_string()),
+        )
+        .arg(
+            Arg::new("skewdirection")
+                .long("skew-direction")
+                .value_name("DIRECTION")
+                .help("Stride skew direction")
+                .value_parser(value_parser!(MoshSkewDirection))
+                .default_value("random"),
+        )
         .arg(
             Arg::new("seed")
                 .short('s')
@@ -171,6 +652,113 @@ fn arg_matches() -> (ArgMatches, MoshCore) {
                 .value_parser(value_parser!(u64))
                 .default_value(container.options.seed.to_string()),
         )
+        .arg(
+            Arg::new("filterglitch")
+                .short('g')
+                .long("filter-glitch")
+                .value_name("COUNT")
+                .help("Filtered stream corruptions")
+                .long_help(
+                    "Corrupt the filtered PNG stream before reconstructing pixels \
+                    (pnglitch), 0 disables it",
+                )
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.filter_glitch.to_string()),
+        )
+        .arg(
+            Arg::new("filter")
+                .long("filter")
+                .value_name("FILTER")
+                .help("Scanline filter")
+                .long_help("Filter rows are encoded with before corruption, picked for each row by default")
+                .value_parser(
+                    PossibleValuesParser::new(["random", "none", "sub", "up", "average", "paeth"])
+                        // Random filters are picked for each row
+                        .map(|filter| MoshFilterType::from_str(&filter, false).ok()),
+                )
+                .default_value("random"),
+        )
+        .arg(
+            Arg::new("filtermismatch")
+                .long("filter-mismatch")
+                .value_name("MISMATCH")
+                .help("Wrong filter decoding rate")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.filter_mismatch.to_string()),
+        )
+        .arg(
+            Arg::new("deflateglitch")
+                .short('z')
+                .long("deflate-glitch")
+                .value_name("COUNT")
+                .help("Compressed stream corruptions")
+                .long_help(
+                    "Corrupt bytes of the compressed (IDAT) stream, rows that can not be \
+                    decoded are restored from the original image, 0 disables it",
+                )
+                .value_parser(value_parser!(u16))
+                .default_value(container.options.deflate_glitch.to_string()),
+        )
+        .arg(
+            Arg::new("mix")
+                .short('x')
+                .long("mix")
+                .value_name("MIX")
+                .help("Opacity over the original image")
+                .value_parser(value_parser!(f64))
+                .default_value(container.options.mix.to_string()),
+        )
+        .arg(
+            Arg::new("blend")
+                .long("blend")
+                .value_name("MODE")
+                .help("Blend mode")
+                .long_help("Blend the result over the original image")
+                .value_parser(value_parser!(MoshBlend))
+                .default_value("normal"),
+        )
+        .arg(
+            Arg::new("preservealpha")
+                .short('a')
+                .long("preserve-alpha")
+                .help("Keep the alpha channel intact")
+                .long_help("Exclude the alpha channel from effects and pixelation")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("orientation")
+                .short('e')
+                .long("orientation")
+                .value_name("ORIENTATION")
+                .help("Effect direction")
+                .long_help("Run effects along rows, columns or either, picked for each chunk")
+                .value_parser(value_parser!(MoshOrientation))
+                .default_value("horizontal"),
+        )
+        .arg(
+            Arg::new("independentstreams")
+                .short('d')
+                .long("independent-streams")
+                .help("Independent effect streams")
+                .long_help(
+                    "Draw chunk placement and each effect from its own random stream, \
+                    so changing one rate leaves the other effects intact",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("indexed")
+                .short('i')
+                .long("indexed")
+                .value_name("MODE")
+                .help("Indexed image mode")
+                .long_help(
+                    "Expand the palette to RGB/A or glitch the indices, \
+                    the palette or both",
+                )
+                .value_parser(value_parser!(IndexedMode))
+                .default_value("expand"),
+        )
         .arg(
             Arg::new("batch")
                 .short('b')
@@ -182,6 +770,7 @@ fn arg_matches() -> (ArgMatches, MoshCore) {
                 .value_parser(value_parser!(u8))
                 .default_value("1"),
         )
+        .args(recipe_args())
         .arg(
             Arg::new("output")
                 .short('o')
@@ -195,23 +784,267 @@ fn arg_matches() -> (ArgMatches, MoshCore) {
     (matches.get_matches(), container)
 }
 
-fn args() -> (PathBuf, String, MoshCore, u8) {
+// Recipes need the `serde` feature
+#[cfg(feature = "serde")]
+fn recipe_args() -> [Arg; 2] {
+    [
+        Arg::new("recipe")
+            .long("recipe")
+            .value_name("RECIPE")
+            .help("Load settings from a recipe file")
+            .long_help(
+                "Load settings from a TOML (or JSON) recipe file, \
+                options set on the command line take precedence over the stored plan \
+                and batches are replanned for each seed",
+            )
+            .value_parser(value_parser!(PathBuf)),
+        Arg::new("saverecipe")
+            .long("save-recipe")
+            .value_name("RECIPE")
+            .help("Save settings to a recipe file")
+            .long_help("Save settings and the plan of the first output to a recipe file")
+            .value_parser(value_parser!(PathBuf)),
+    ]
+}
+
+#[cfg(not(feature = "serde"))]
+fn recipe_args() -> [Arg; 0] {
+    []
+}
+
+#[cfg(feature = "serde")]
+fn recipe(matches: &ArgMatches) -> Option<(MoshOptions, Option<MoshPlan>)> {
+    let file = matches.get_one::<PathBuf>("recipe")?;
+
+    match read_recipe(file) {
+        Ok(recipe) => Some((recipe.options, recipe.plan)),
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1)
+        }
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn recipe(_matches: &ArgMatches) -> Option<(MoshOptions, Option<MoshPlan>)> {
+    None
+}
+
+// Recipe values are kept unless set on the command line
+fn arg<T: Clone + Send + Sync + 'static>(
+    matches: &ArgMatches,
+    id: &str,
+    value: &mut T,
+    recipe: bool,
+) -> bool {
+    let is_set = matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    if !recipe || is_set {
+        value.clone_from(matches.get_one::<T>(id).unwrap());
+    }
+
+    is_set
+}
+
+fn args() -> Args {
     let (matches, mut container) = arg_matches();
     let input = matches.get_one::<PathBuf>("file").unwrap();
     let output = matches.get_one::<String>("output").unwrap();
     let batch = matches.get_one::<u8>("batch").unwrap();
+    let (recipe, mut plan) = match recipe(&matches) {
+        Some((options, plan)) => {
+            container.options = options;
+            (true, plan)
+        }
+        None => (false, None),
+    };
+
+    let options = &mut container.options;
 
-    container.options.min_rate = *matches.get_one::<u16>("minrate").unwrap();
-    container.options.max_rate = *matches.get_one::<u16>("maxrate").unwrap();
-    container.options.pixelation = *matches.get_one::<u8>("pixelation").unwrap();
-    container.options.line_shift = *matches.get_one::<f64>("lineshift").unwrap();
-    container.options.reverse = *matches.get_one::<f64>("reverse").unwrap();
-    container.options.flip = *matches.get_one::<f64>("flip").unwrap();
-    container.options.channel_swap = *matches.get_one::<f64>("channelswap").unwrap();
-    container.options.channel_shift = *matches.get_one::<f64>("channelshift").unwrap();
-    container.options.seed = *matches.get_one::<u64>("seed").unwrap();
-
-    (input.to_path_buf(), output.to_string(), container, *batch)
+    // Plans are replaced if any option is overridden
+    let is_set = [
+        arg(&matches, "minrate", &mut options.min_rate, recipe),
+        arg(&matches, "maxrate", &mut options.max_rate, recipe),
+        arg(
+            &matches,
+            "minchunkheight",
+            &mut options.min_chunk_height,
+            recipe,
+        ),
+        arg(
+            &matches,
+            "maxchunkheight",
+            &mut options.max_chunk_height,
+            recipe,
+        ),
+        arg(
+            &matches,
+            "minchunkwidth",
+            &mut options.min_chunk_width,
+            recipe,
+        ),
+        arg(
+            &matches,
+            "maxchunkwidth",
+            &mut options.max_chunk_width,
+            recipe,
+        ),
+        arg(&matches, "pixelation", &mut options.pixelation, recipe),
+        arg(&matches, "lineshift", &mut options.line_shift, recipe),
+        arg(&matches, "reverse", &mut options.reverse, recipe),
+        arg(&matches, "flip", &mut options.flip, recipe),
+        arg(&matches, "channelswap", &mut options.channel_swap, recipe),
+        arg(&matches, "channelshift", &mut options.channel_shift, recipe),
+        arg(&matches, "shiftunit", &mut options.shift_unit, recipe),
+        arg(&matches, "colorspace", &mut options.color_space, recipe),
+        arg(&matches, "bitflip", &mut options.bit_flip, recipe),
+        arg(
+            &matches,
+            "bitflipintensity",
+            &mut options.bit_flip_intensity,
+            recipe,
+        ),
+        arg(&matches, "bytereplace", &mut options.byte_replace, recipe),
+        arg(
+            &matches,
+            "bytereplaceintensity",
+            &mut options.byte_replace_intensity,
+            recipe,
+        ),
+        arg(&matches, "byteinsert", &mut options.byte_insert, recipe),
+        arg(
+            &matches,
+            "byteinsertintensity",
+            &mut options.byte_insert_intensity,
+            recipe,
+        ),
+        arg(&matches, "bytedelete", &mut options.byte_delete, recipe),
+        arg(
+            &matches,
+            "bytedeleteintensity",
+            &mut options.byte_delete_intensity,
+            recipe,
+        ),
+        arg(&matches, "sort", &mut options.sort, recipe),
+        arg(&matches, "sortkey", &mut options.sort_key, recipe),
+        arg(&matches, "sortruns", &mut options.sort_runs, recipe),
+        arg(&matches, "sortmin", &mut options.sort_min, recipe),
+        arg(&matches, "sortmax", &mut options.sort_max, recipe),
+        arg(&matches, "sortinterval", &mut options.sort_interval, recipe),
+        arg(&matches, "rgbsplit", &mut options.rgb_split, recipe),
+        arg(
+            &matches,
+            "rgbsplitrange",
+            &mut options.rgb_split_range,
+            recipe,
+        ),
+        arg(
+            &matches,
+            "rgbsplitoffsets",
+            &mut options.rgb_split_offsets,
+            recipe,
+        ),
+        arg(
+            &matches,
+            "rgbsplitedge",
+            &mut options.rgb_split_edge,
+            recipe,
+        ),
+        arg(&matches, "wave", &mut options.wave, recipe),
+        arg(&matches, "waveshape", &mut options.wave_shape, recipe),
+        arg(
+            &matches,
+            "waveamplitude",
+            &mut options.wave_amplitude,
+            recipe,
+        ),
+        arg(&matches, "waveperiod", &mut options.wave_period, recipe),
+        arg(&matches, "wavephase", &mut options.wave_phase, recipe),
+        arg(&matches, "blocks", &mut options.blocks, recipe),
+        arg(&matches, "blocksize", &mut options.block_size, recipe),
+        arg(
+            &matches,
+            "blockdisplacement",
+            &mut options.block_displacement,
+            recipe,
+        ),
+        arg(&matches, "smear", &mut options.smear, recipe),
+        arg(&matches, "smearlength", &mut options.smear_length, recipe),
+        arg(
+            &matches,
+            "smeardirection",
+            &mut options.smear_direction,
+            recipe,
+        ),
+        arg(&matches, "smearpartial", &mut options.smear_partial, recipe),
+        arg(&matches, "chunkcopy", &mut options.chunk_copy, recipe),
+        arg(&matches, "chunkswap", &mut options.chunk_swap, recipe),
+        arg(&matches, "chunktile", &mut options.chunk_tile, recipe),
+        arg(
+            &matches,
+            "chunktilecount",
+            &mut options.chunk_tile_count,
+            recipe,
+        ),
+        arg(&matches, "crush", &mut options.crush, recipe),
+        arg(&matches, "crushbits", &mut options.crush_bits, recipe),
+        arg(&matches, "crushdither", &mut options.crush_dither, recipe),
+        arg(&matches, "crushglobal", &mut options.crush_global, recipe),
+        arg(&matches, "skew", &mut options.skew, recipe),
+        arg(&matches, "skewrange", &mut options.skew_range, recipe),
+        arg(
+            &matches,
+            "skewdirection",
+            &mut options.skew_direction,
+            recipe,
+        ),
+        arg(&matches, "seed", &mut options.seed, recipe),
+        arg(&matches, "filterglitch", &mut options.filter_glitch, recipe),
+        arg(&matches, "filter", &mut options.filter, recipe),
+        arg(
+            &matches,
+            "filtermismatch",
+            &mut options.filter_mismatch,
+            recipe,
+        ),
+        arg(
+            &matches,
+            "deflateglitch",
+            &mut options.deflate_glitch,
+            recipe,
+        ),
+        arg(&matches, "mix", &mut options.mix, recipe),
+        arg(&matches, "blend", &mut options.blend, recipe),
+        arg(
+            &matches,
+            "preservealpha",
+            &mut options.preserve_alpha,
+            recipe,
+        ),
+        arg(&matches, "orientation", &mut options.orientation, recipe),
+        arg(
+            &matches,
+            "independentstreams",
+            &mut options.independent_streams,
+            recipe,
+        ),
+        arg(&matches, "indexed", &mut options.indexed, recipe),
+    ];
+
+    // Batches are replanned for each seed
+    if is_set.contains(&true) || *batch > 1 {
+        plan = None;
+    }
+
+    Args {
+        input: input.to_path_buf(),
+        output: output.to_string(),
+        container,
+        batch: *batch,
+        plan,
+        #[cfg(feature = "serde")]
+        save_recipe: matches.get_one::<PathBuf>("saverecipe").cloned(),
+    }
 }
 
 fn filename(output: &str, index: u8, batch: u8) -> String {
@@ -222,7 +1055,39 @@ fn filename(output: &str, index: u8, batch: u8) -> String {
     }
 }
 
-fn cli(input: PathBuf, output: &str, mut container: MoshCore, batch: u8) {
+fn write(dest: &str, container: &MoshCore) -> Result<(), MoshError> {
+    if container.data.color_type == ColorType::Indexed {
+        write_indexed_file(
+            dest,
+            &container.data.buf,
+            container.data.width,
+            container.data.height,
+            container.data.bit_depth,
+            &container.data.palette_buf,
+            container.data.trns_buf.as_deref(),
+        )
+    } else {
+        write_file(
+            dest,
+            &container.data.buf,
+            container.data.width,
+            container.data.height,
+            container.data.color_type,
+            container.data.bit_depth,
+        )
+    }
+}
+
+fn cli(args: Args) {
+    let Args {
+        input,
+        output,
+        mut container,
+        batch,
+        plan,
+        #[cfg(feature = "serde")]
+        save_recipe,
+    } = args;
     let mut index = 0;
     let spinner = ProgressBar::new_spinner();
     let spinner_style = if cfg!(unix) {
@@ -265,10 +1130,30 @@ fn cli(input: PathBuf, output: &str, mut container: MoshCore, batch: u8) {
 
     spinner.println(format!("mode: {}", color_type(&container)));
 
+    #[cfg(feature = "serde")]
+    if let Some(file) = save_recipe {
+        let recipe = match &plan {
+            Some(plan) => Ok(plan.clone()),
+            None => container.plan(),
+        }
+        .map(|plan| MoshRecipe::new(container.options.clone(), Some(plan)));
+
+        if let Err(error) = recipe.and_then(|recipe| write_recipe(file, &recipe)) {
+            spinner.finish_with_message("\x1b[1;31mERROR\x1b[0m");
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    }
+
     for _ in 0..batch {
         spinner.set_message("\x1b[94mprocessing\x1b[0m");
 
-        if let Err(error) = container.mosh() {
+        let result = match &plan {
+            Some(plan) => container.apply(plan),
+            None => container.mosh(),
+        };
+
+        if let Err(error) = result {
             spinner.finish_with_message("\x1b[1;31mERROR\x1b[0m");
             eprintln!("{error}");
             std::process::exit(1);
@@ -278,14 +1163,7 @@ fn cli(input: PathBuf, output: &str, mut container: MoshCore, batch: u8) {
         container.options.seed += 1;
         spinner.set_message("\x1b[33mwriting output\x1b[0m");
 
-        if let Err(error) = write_file(
-            &filename(output, index, batch),
-            &container.data.buf,
-            container.data.width,
-            container.data.height,
-            container.data.color_type,
-            container.data.bit_depth,
-        ) {
+        if let Err(error) = write(&filename(&output, index, batch), &container) {
             spinner.finish_with_message("\x1b[1;31mERROR\x1b[0m");
             eprintln!("{error}");
             std::process::exit(1);
@@ -296,6 +1174,5 @@ fn cli(input: PathBuf, output: &str, mut container: MoshCore, batch: u8) {
 }
 
 pub fn start() {
-    let (input, output, container, series) = args();
-    cli(input, &output, container, series);
+    cli(args());
 }
@@ -0,0 +1,63 @@
+// Compressed (IDAT) stream corruption
+
+use std::ops::Range;
+
+const SIGNATURE_SIZE: usize = 8;
+const ZLIB_HEADER_SIZE: usize = 2;
+
+// Overwrites bytes of the concatenated IDAT data.
+// Positions are fractions of the data past the zlib header.
+pub fn corrupt(png: &mut [u8], ops: &[(f64, u8)]) {
+    let ranges = idat_ranges(png);
+    let data_size = ranges.iter().map(ExactSizeIterator::len).sum::<usize>();
+
+    if data_size <= ZLIB_HEADER_SIZE {
+        return;
+    }
+
+    let corruptible = data_size - ZLIB_HEADER_SIZE;
+
+    for (position, value) in ops {
+        let offset = (position.clamp(0.0, 1.0) * corruptible as f64) as usize;
+        let mut offset = ZLIB_HEADER_SIZE + offset.min(corruptible - 1);
+
+        for range in &ranges {
+            if offset < range.len() {
+                png[range.start + offset] = *value;
+                break;
+            }
+
+            offset -= range.len();
+        }
+    }
+}
+
+fn idat_ranges(png: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut position = SIGNATURE_SIZE;
+
+    // Length, type, data and CRC
+    while position + 8 <= png.len() {
+        let length = u32::from_be_bytes([
+            png[position],
+            png[position + 1],
+            png[position + 2],
+            png[position + 3],
+        ]) as usize;
+
+        let start = position + 8;
+        let end = start + length;
+
+        if end > png.len() {
+            break;
+        }
+
+        if &png[position + 4..start] == b"IDAT" {
+            ranges.push(start..end);
+        }
+
+        position = end + 4;
+    }
+
+    ranges
+}
@@ -19,6 +19,15 @@ pub enum MoshError {
     OutOfMemory,
     /// Unsupported color type.
     UnsupportedColorType,
+    /// Recipe can not be parsed or serialized.
+    RecipeError(String),
+    /// Option value is out of its allowed range.
+    InvalidParameters {
+        /// Option name.
+        field: &'static str,
+        /// Allowed range.
+        range: String,
+    },
 }
 
 impl std::error::Error for MoshError {}
@@ -31,6 +40,10 @@ impl Display for MoshError {
             Self::IoError(e) => Display::fmt(e, f),
             Self::OutOfMemory => f.write_str("Out of memory"),
             Self::UnsupportedColorType => f.write_str("Unsupported color type"),
+            Self::RecipeError(e) => f.write_str(e),
+            Self::InvalidParameters { field, range } => {
+                write!(f, "Invalid `{field}` value, allowed range is {range}")
+            }
         }
     }
 }
@@ -0,0 +1,91 @@
+// PNG scanline filters, see https://www.w3.org/TR/png/#9Filters
+
+use crate::MoshFilterType;
+
+// Filters rows of `line_size` bytes, `bpp` is the pixel size in bytes (rounded up).
+// Rows cycle through `filters`, rows are left unfiltered if there are none.
+pub fn filter(buf: &[u8], line_size: usize, bpp: usize, filters: &[MoshFilterType]) -> Vec<u8> {
+    let mut output = vec![0_u8; buf.len()];
+    let zero = vec![0_u8; line_size];
+
+    for (index, (row, filtered)) in buf
+        .chunks_exact(line_size)
+        .zip(output.chunks_exact_mut(line_size))
+        .enumerate()
+    {
+        let prior = match index {
+            0 => &zero,
+            _ => &buf[(index - 1) * line_size..index * line_size],
+        };
+        let filter = row_filter(filters, index);
+
+        for x in 0..line_size {
+            let (a, c) = if x >= bpp {
+                (row[x - bpp], prior[x - bpp])
+            } else {
+                (0, 0)
+            };
+
+            filtered[x] = row[x].wrapping_sub(predict(filter, a, prior[x], c));
+        }
+    }
+
+    output
+}
+
+// Reconstructs filtered rows in place
+pub fn unfilter(buf: &mut [u8], line_size: usize, bpp: usize, filters: &[MoshFilterType]) {
+    let zero = vec![0_u8; line_size];
+    let line_count = buf.len() / line_size;
+
+    for index in 0..line_count {
+        let (done, rest) = buf.split_at_mut(index * line_size);
+        let row = &mut rest[..line_size];
+        let prior = match index {
+            0 => &zero,
+            _ => &done[(index - 1) * line_size..],
+        };
+        let filter = row_filter(filters, index);
+
+        for x in 0..line_size {
+            let (a, c) = if x >= bpp {
+                (row[x - bpp], prior[x - bpp])
+            } else {
+                (0, 0)
+            };
+
+            row[x] = row[x].wrapping_add(predict(filter, a, prior[x], c));
+        }
+    }
+}
+
+fn row_filter(filters: &[MoshFilterType], index: usize) -> MoshFilterType {
+    match filters.len() {
+        0 => MoshFilterType::None,
+        count => filters[index % count],
+    }
+}
+
+// `a` is the byte to the left, `b` above and `c` above the left one
+fn predict(filter: MoshFilterType, a: u8, b: u8, c: u8) -> u8 {
+    match filter {
+        MoshFilterType::None => 0,
+        MoshFilterType::Sub => a,
+        MoshFilterType::Up => b,
+        MoshFilterType::Average => ((u16::from(a) + u16::from(b)) / 2) as u8,
+        MoshFilterType::Paeth => {
+            let p = i16::from(a) + i16::from(b) - i16::from(c);
+            let pa = (p - i16::from(a)).abs();
+            let pb = (p - i16::from(b)).abs();
+            let pc = (p - i16::from(c)).abs();
+
+            if pa <= pb && pa <= pc {
+                a
+            } else if pb <= pc {
+                b
+            } else {
+                c
+            }
+        }
+    }
+}
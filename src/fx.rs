@@ -1,25 +1,305 @@
 //! Effects
 
-/// Mutates provided bytes
-pub trait Mosh {
-    fn glitch(&self, value: &mut [u8]);
+use std::{
+    cmp,
+    f64::consts::TAU,
+    ops::{BitXor, Mul, Range, Shl},
+};
+
+use crate::{MoshColorSpace, MoshWaveShape};
+
+/// Mutates provided samples
+///
+/// Samples are bytes for 8-bit images and `u16` values for 16-bit images.
+pub trait Mosh<T = u8> {
+    fn glitch(&self, value: &mut [T]);
 }
 
 /// Chunk mutations
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MoshChunk {
     ChannelSwap(usize, usize, usize),
     Flip,
 }
 
 /// Line mutations within a chunk
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MoshLine {
     ChannelShift(usize, usize, usize),
     Shift(usize),
     Reverse,
 }
 
-impl Mosh for MoshChunk {
-    fn glitch(&self, chunk: &mut [u8]) {
+/// Converts the first three channels of each pixel from RGB to another color space
+///
+/// Channels keep their order: Y, Cb, Cr or hue, saturation, value.
+/// Other channels (alpha) are left alone, as are pixels with less than three channels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshColor {
+    /// Channels per pixel.
+    pub channel_count: usize,
+    /// Color space.
+    pub space: MoshColorSpace,
+    /// Converts back to RGB instead.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub decode: bool,
+}
+
+/// Byte mutations within a chunk
+///
+/// Mutations work on samples, not on raw bytes: 16-bit samples are mutated whole,
+/// with byte values scaled by 257 and bit positions wrapping at 16.
+/// Samples of 8 bits and below wrap at 8.
+///
+/// * `BitFlip(position, bit)` flips a bit of a sample.
+/// * `Replace(position, length, pattern)` overwrites samples with a repeating pattern.
+/// * `Insert(position, bytes)` inserts samples, the rest of the chunk drifts forward.
+/// * `Delete(position, length)` deletes samples, the rest of the chunk drifts back.
+///
+/// Insertion and deletion keep the chunk length: samples pushed past its end are
+/// dropped and the end is zeroed after a deletion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoshByte {
+    BitFlip(usize, u8),
+    Replace(usize, usize, Vec<u8>),
+    Insert(usize, Vec<u8>),
+    Delete(usize, usize),
+}
+
+/// Pixel sorting key
+///
+/// Keys are scaled to `0..=255`, grayscale pixels have no hue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MoshSortKey {
+    #[default]
+    Luminance,
+    Hue,
+    Channel(usize),
+}
+
+/// Pixel runs to sort
+///
+/// * `Threshold(min, max)` sorts runs of pixels with keys within `min..=max`.
+/// * `Interval(lengths)` sorts runs of given lengths (in pixels), repeated through the line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MoshSortRuns {
+    Threshold(u8, u8),
+    Interval(Vec<usize>),
+}
+
+/// Sorts pixel runs within a line
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshSort {
+    /// Channels per pixel.
+    pub channel_count: usize,
+    /// Sample bit depth.
+    pub depth: u8,
+    /// Sorting key.
+    pub key: MoshSortKey,
+    /// Runs to sort.
+    pub runs: MoshSortRuns,
+}
+
+/// Edge handling of displaced pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshEdge {
+    /// Repeats the nearest pixel.
+    #[default]
+    Clamp,
+    /// Wraps around the chunk.
+    Wrap,
+    /// Zeroes the channel and the alpha channel, if any.
+    Transparent,
+}
+
+/// Displaces channels of a chunk (RGB split)
+///
+/// Offsets are in pixels, channels without one stay in place.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshSplit {
+    /// Line size, in samples.
+    pub line_size: usize,
+    /// Channels per pixel.
+    pub channel_count: usize,
+    /// Horizontal and vertical offsets, per channel.
+    pub offsets: Vec<(i32, i32)>,
+    /// Edge handling.
+    pub edge: MoshEdge,
+}
+
+/// Displaces lines of a chunk along a wave
+///
+/// Positive offsets move lines forward, wrapping around them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshWave {
+    /// Line size, in samples.
+    pub line_size: usize,
+    /// Channels per pixel.
+    pub channel_count: usize,
+    /// Wave shape.
+    pub shape: MoshWaveShape,
+    /// Largest offset, in pixels.
+    pub amplitude: usize,
+    /// Wave length, in lines.
+    pub period: usize,
+    /// Lines the wave starts at.
+    pub phase: usize,
+    /// Offsets (in pixels) the noise passes through, one period apart.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub noise: Vec<i32>,
+}
+
+/// Macroblock mutations
+///
+/// Blocks are addressed by column and row, in blocks.
+///
+/// * `Displace(block, x, y)` fills a block from pixels offset by `x` and `y`, like a broken
+///   motion vector.
+/// * `Duplicate(from, to)` copies a block over another one.
+/// * `Swap(first, second)` swaps two blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoshBlockOp {
+    Displace((usize, usize), i32, i32),
+    Duplicate((usize, usize), (usize, usize)),
+    Swap((usize, usize), (usize, usize)),
+}
+
+/// Mutates square blocks of a chunk (macroblocks)
+///
+/// Only whole blocks are mutated, the rest of the chunk stays in place.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshBlocks {
+    /// Line size, in samples.
+    pub line_size: usize,
+    /// Channels per pixel.
+    pub channel_count: usize,
+    /// Block size, in pixels.
+    pub size: usize,
+    /// Mutations, in order.
+    pub ops: Vec<MoshBlockOp>,
+}
+
+/// Copies a line (or a pixel) of a chunk over the following ones (pixel drag)
+///
+/// Runs on the whole chunk, as streaks cross lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshSmear {
+    /// Line size, in samples.
+    pub line_size: usize,
+    /// Channels per pixel.
+    pub channel_count: usize,
+    /// Copies the source line over the following lines, instead of the source pixel over the
+    /// following pixels of each line.
+    pub vertical: bool,
+    /// Copied line, or pixel of each line.
+    pub source: usize,
+    /// Streak length, in lines or pixels.
+    pub length: usize,
+    /// Smeared pixels of each line (or smeared lines), all of them when `None`.
+    ///
+    /// Spans are clipped to the chunk, reversed spans are empty.
+    pub span: Option<(usize, usize)>,
+}
+
+/// Reads a chunk as if its lines were longer (or shorter), then writes it back (stride skew)
+///
+/// Each line drifts by `skew` samples more than the previous one, wrapping around the chunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshSkew {
+    /// Line size, in samples.
+    pub line_size: usize,
+    /// Stride error, in samples.
+    pub skew: isize,
+}
+
+/// Dithering of quantized samples
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshDither {
+    /// Rounds to the nearest level.
+    #[default]
+    None,
+    /// Offsets samples by a 4x4 Bayer matrix.
+    Ordered,
+    /// Spreads rounding errors over the following pixels (Floyd-Steinberg).
+    Diffusion,
+}
+
+/// Reduces bits per channel of a chunk (bitcrush)
+///
+/// Levels are spread over the whole sample range, channels missing from `bits`
+/// use the last value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshCrush {
+    /// Line size, in samples.
+    pub line_size: usize,
+    /// Channels per pixel.
+    pub channel_count: usize,
+    /// Sample bit depth.
+    pub depth: u8,
+    /// Kept bits, per channel.
+    pub bits: Vec<u8>,
+    /// Dithering.
+    pub dither: MoshDither,
+}
+
+/// Chunk relocations within the image
+///
+/// Lines are image lines, chunks keep their columns.
+///
+/// * `Copy(line)` copies the chunk to another line.
+/// * `Swap(line)` swaps the chunk with the region at another line, unless they overlap.
+/// * `Tile(count)` repeats the chunk below itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoshRegion {
+    Copy(usize),
+    Swap(usize),
+    Tile(usize),
+}
+
+/// Filtered stream mutations
+///
+/// * `Graffiti(position, bytes)` overwrites bytes.
+/// * `Replace(position, length, from, to)` replaces a byte value within a range.
+/// * `Transpose(first, second, length)` swaps two ranges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoshFilterOp {
+    Graffiti(usize, Vec<u8>),
+    Replace(usize, usize, u8, u8),
+    Transpose(usize, usize, usize),
+}
+
+impl<T> Mosh<T> for MoshChunk {
+    fn glitch(&self, chunk: &mut [T]) {
         match self {
             Self::ChannelSwap(channel_1, channel_2, channel_count) => {
                 let chunk_length = chunk.len();
@@ -38,8 +318,8 @@ impl Mosh for MoshChunk {
     }
 }
 
-impl Mosh for MoshLine {
-    fn glitch(&self, line: &mut [u8]) {
+impl<T> Mosh<T> for MoshLine {
+    fn glitch(&self, line: &mut [T]) {
         match self {
             Self::ChannelShift(amount, channel, channel_count) => {
                 let line_length = line.len();
@@ -48,14 +328,661 @@ impl Mosh for MoshLine {
                 for i in 0..channel_value_count {
                     let current_index = (i * channel_count + channel) % line_length;
                     let target_index =
-                        (i * channel_count + channel + (channel + 1) * amount) % line_length;
+                        (i * channel_count + channel + (channel + 1) * (amount % line_length))
+                            % line_length;
 
                     line.swap(current_index, target_index);
                 }
             }
 
-            Self::Shift(amount) => line.rotate_left(*amount),
+            Self::Shift(amount) => {
+                if !line.is_empty() {
+                    line.rotate_left(amount % line.len());
+                }
+            }
             Self::Reverse => line.reverse(),
         }
     }
 }
+
+impl MoshColor {
+    fn convert<T: Copy + Into<f64>>(&self, chunk: &mut [T], max: f64, sample: impl Fn(f64) -> T) {
+        if self.channel_count < 3 || self.space == MoshColorSpace::Rgb {
+            return;
+        }
+
+        for pixel in chunk.chunks_exact_mut(self.channel_count) {
+            let [a, b, c] = [0, 1, 2].map(|channel| pixel[channel].into() / max);
+            let converted = match (self.space, self.decode) {
+                (MoshColorSpace::YCbCr, false) => ycbcr(a, b, c),
+                (MoshColorSpace::YCbCr, true) => ycbcr_rgb(a, b, c),
+                (MoshColorSpace::Hsv, false) => hsv(a, b, c),
+                (MoshColorSpace::Hsv, true) => hsv_rgb(a, b, c),
+                (MoshColorSpace::Rgb, _) => [a, b, c],
+            };
+
+            for (channel, value) in pixel.iter_mut().zip(converted) {
+                *channel = sample((value.clamp(0.0, 1.0) * max).round());
+            }
+        }
+    }
+}
+
+impl Mosh<u8> for MoshColor {
+    fn glitch(&self, chunk: &mut [u8]) {
+        self.convert(chunk, f64::from(u8::MAX), |value| value as u8);
+    }
+}
+
+impl Mosh<u16> for MoshColor {
+    fn glitch(&self, chunk: &mut [u16]) {
+        self.convert(chunk, f64::from(u16::MAX), |value| value as u16);
+    }
+}
+
+// Full-range BT.601, as in JPEG
+fn ycbcr(red: f64, green: f64, blue: f64) -> [f64; 3] {
+    [
+        0.299 * red + 0.587 * green + 0.114 * blue,
+        0.5 - 0.168_736 * red - 0.331_264 * green + 0.5 * blue,
+        0.5 + 0.5 * red - 0.418_688 * green - 0.081_312 * blue,
+    ]
+}
+
+fn ycbcr_rgb(luma: f64, blue: f64, red: f64) -> [f64; 3] {
+    let (blue, red) = (blue - 0.5, red - 0.5);
+
+    [
+        luma + 1.402 * red,
+        luma - 0.344_136 * blue - 0.714_136 * red,
+        luma + 1.772 * blue,
+    ]
+}
+
+// Hue within `0..1`
+fn hsv(red: f64, green: f64, blue: f64) -> [f64; 3] {
+    let max = red.max(green).max(blue);
+    let delta = max - red.min(green).min(blue);
+    let saturation = if max > 0.0 { delta / max } else { 0.0 };
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == red {
+        ((green - blue) / delta).rem_euclid(6.0)
+    } else if max == green {
+        (blue - red) / delta + 2.0
+    } else {
+        (red - green) / delta + 4.0
+    };
+
+    [hue / 6.0, saturation, max]
+}
+
+fn hsv_rgb(hue: f64, saturation: f64, value: f64) -> [f64; 3] {
+    let hue = hue * 6.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (red, green, blue) = match hue as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let offset = value - chroma;
+
+    [red + offset, green + offset, blue + offset]
+}
+
+impl MoshByte {
+    fn mutate<T>(&self, chunk: &mut [T], bits: u32, scale: T)
+    where
+        T: Copy + From<u8> + BitXor<Output = T> + Shl<u32, Output = T> + Mul<Output = T>,
+    {
+        let chunk_length = chunk.len();
+        let value = |byte: &u8| T::from(*byte) * scale;
+
+        match self {
+            Self::BitFlip(position, bit) => {
+                if let Some(sample) = chunk.get_mut(*position) {
+                    *sample = *sample ^ (T::from(1) << (u32::from(*bit) % bits));
+                }
+            }
+
+            Self::Replace(position, length, pattern) => {
+                let start = cmp::min(*position, chunk_length);
+                let end = cmp::min(start.saturating_add(*length), chunk_length);
+
+                for (sample, byte) in chunk[start..end].iter_mut().zip(pattern.iter().cycle()) {
+                    *sample = value(byte);
+                }
+            }
+
+            Self::Insert(position, bytes) => {
+                let start = cmp::min(*position, chunk_length);
+                let length = cmp::min(bytes.len(), chunk_length - start);
+
+                chunk.copy_within(start..chunk_length - length, start + length);
+
+                for (sample, byte) in chunk[start..start + length].iter_mut().zip(bytes) {
+                    *sample = value(byte);
+                }
+            }
+
+            Self::Delete(position, length) => {
+                let start = cmp::min(*position, chunk_length);
+                let length = cmp::min(*length, chunk_length - start);
+
+                chunk.copy_within(start + length.., start);
+                chunk[chunk_length - length..].fill(T::from(0));
+            }
+        }
+    }
+}
+
+impl Mosh<u8> for MoshByte {
+    fn glitch(&self, chunk: &mut [u8]) {
+        self.mutate(chunk, u8::BITS, 1);
+    }
+}
+
+impl Mosh<u16> for MoshByte {
+    fn glitch(&self, chunk: &mut [u16]) {
+        self.mutate(chunk, u16::BITS, 257);
+    }
+}
+
+impl MoshSort {
+    fn sort<T: Copy + Into<u32>>(&self, line: &mut [T], bits: u32) {
+        let channel_count = cmp::max(self.channel_count, 1);
+        let line_size = line.len() - line.len() % channel_count;
+        let line = &mut line[..line_size];
+        let keys: Vec<u8> = line
+            .chunks_exact(channel_count)
+            .map(|pixel| self.key(pixel, bits))
+            .collect();
+
+        let pixel_count = keys.len();
+        let mut runs: Vec<Range<usize>> = Vec::new();
+
+        if let MoshSortRuns::Threshold(min, max) = self.runs {
+            let mut start = None;
+
+            for (index, key) in keys.iter().chain([&0]).enumerate() {
+                let inside = index < pixel_count && (min..=max).contains(key);
+
+                match (start, inside) {
+                    (None, true) => start = Some(index),
+                    (Some(first), false) => {
+                        runs.push(first..index);
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+        } else {
+            let mut lengths = match &self.runs {
+                MoshSortRuns::Interval(lengths) => lengths.as_slice(),
+                MoshSortRuns::Threshold(..) => &[],
+            }
+            .iter()
+            .filter(|length| **length > 0)
+            .cycle();
+            let mut start = 0;
+
+            while start < pixel_count {
+                let end = lengths
+                    .next()
+                    .map_or(pixel_count, |length| start.saturating_add(*length));
+
+                runs.push(start..cmp::min(end, pixel_count));
+                start = end;
+            }
+        }
+
+        for run in runs {
+            let mut order: Vec<usize> = run.clone().collect();
+            order.sort_by_key(|index| keys[*index]);
+
+            let sorted: Vec<T> = order
+                .iter()
+                .flat_map(|index| &line[index * channel_count..(index + 1) * channel_count])
+                .copied()
+                .collect();
+
+            line[run.start * channel_count..run.end * channel_count].copy_from_slice(&sorted);
+        }
+    }
+
+    fn key<T: Copy + Into<u32>>(&self, pixel: &[T], bits: u32) -> u8 {
+        let value = |channel: usize| {
+            let sample = pixel[channel].into();
+
+            if bits >= 8 {
+                (sample >> (bits - 8)) as u8
+            } else {
+                (sample * 255 / ((1 << bits) - 1)) as u8
+            }
+        };
+
+        match self.key {
+            MoshSortKey::Luminance if pixel.len() >= 3 => {
+                let luminance = 299 * u32::from(value(0))
+                    + 587 * u32::from(value(1))
+                    + 114 * u32::from(value(2));
+
+                (luminance / 1000) as u8
+            }
+
+            MoshSortKey::Luminance => value(0),
+            MoshSortKey::Hue if pixel.len() >= 3 => hue(value(0), value(1), value(2)),
+            MoshSortKey::Hue => 0,
+            MoshSortKey::Channel(channel) => value(cmp::min(channel, pixel.len() - 1)),
+        }
+    }
+}
+
+impl Mosh<u8> for MoshSort {
+    fn glitch(&self, line: &mut [u8]) {
+        self.sort(line, u32::from(self.depth).clamp(1, 8));
+    }
+}
+
+impl Mosh<u16> for MoshSort {
+    fn glitch(&self, line: &mut [u16]) {
+        self.sort(line, 16);
+    }
+}
+
+impl<T: Copy> Mosh<T> for MoshSkew {
+    fn glitch(&self, chunk: &mut [T]) {
+        let chunk_length = chunk.len();
+
+        if self.line_size == 0 || self.skew == 0 || chunk_length == 0 {
+            return;
+        }
+
+        let source = chunk.to_vec();
+        // Wide enough for any stride, positions wrap around the chunk anyway
+        let stride = self.line_size as i128 + self.skew as i128;
+
+        for (line, values) in chunk.chunks_mut(self.line_size).enumerate() {
+            let start = (line as i128 * stride).rem_euclid(chunk_length as i128);
+
+            for (column, value) in values.iter_mut().enumerate() {
+                let position = (start + column as i128).rem_euclid(chunk_length as i128);
+                *value = source[position as usize];
+            }
+        }
+    }
+}
+
+impl MoshCrush {
+    const BAYER: [[f64; 4]; 4] = [
+        [0.0, 8.0, 2.0, 10.0],
+        [12.0, 4.0, 14.0, 6.0],
+        [3.0, 11.0, 1.0, 9.0],
+        [15.0, 7.0, 13.0, 5.0],
+    ];
+
+    fn crush<T: Copy + Into<f64>>(&self, chunk: &mut [T], bits: u8, sample: impl Fn(f64) -> T) {
+        let channel_count = self.channel_count.clamp(1, cmp::max(chunk.len(), 1));
+        let depth = cmp::min(self.depth, bits);
+        let max = f64::from((1_u32 << depth) - 1);
+
+        if self.line_size == 0 || self.bits.is_empty() {
+            return;
+        }
+
+        // Levels of a channel, `None` when no bits are dropped
+        let levels: Vec<Option<f64>> = (0..channel_count)
+            .map(|channel| {
+                let kept = cmp::max(self.bits[cmp::min(channel, self.bits.len() - 1)], 1);
+                (kept < depth).then(|| f64::from((1_u32 << kept) - 1))
+            })
+            .collect();
+
+        let quantize = |value: f64, levels: f64| {
+            ((value / max * levels).round().clamp(0.0, levels) / levels * max).round()
+        };
+
+        let width = self.line_size / channel_count;
+
+        match self.dither {
+            MoshDither::None | MoshDither::Ordered => {
+                for (index, value) in chunk.iter_mut().enumerate() {
+                    let Some(levels) = levels[index % channel_count] else {
+                        continue;
+                    };
+
+                    let bias = if self.dither == MoshDither::Ordered {
+                        let line = index / self.line_size;
+                        let pixel = index % self.line_size / channel_count;
+                        let threshold = (Self::BAYER[line % 4][pixel % 4] + 0.5) / 16.0 - 0.5;
+                        threshold * max / levels
+                    } else {
+                        0.0
+                    };
+
+                    *value = sample(quantize((*value).into() + bias, levels));
+                }
+            }
+
+            MoshDither::Diffusion => {
+                let mut values: Vec<f64> = chunk.iter().map(|value| (*value).into()).collect();
+
+                for index in 0..values.len() {
+                    let Some(levels) = levels[index % channel_count] else {
+                        continue;
+                    };
+
+                    let crushed = quantize(values[index], levels);
+                    let error = values[index] - crushed;
+                    let pixel = index % self.line_size / channel_count;
+                    let below = index + self.line_size;
+                    values[index] = crushed;
+
+                    for (target, weight) in [
+                        ((pixel + 1 < width).then_some(index + channel_count), 7.0),
+                        ((pixel > 0).then(|| below - channel_count), 3.0),
+                        (Some(below), 5.0),
+                        ((pixel + 1 < width).then_some(below + channel_count), 1.0),
+                    ] {
+                        if let Some(value) = target.and_then(|target| values.get_mut(target)) {
+                            *value += error * weight / 16.0;
+                        }
+                    }
+                }
+
+                for (index, value) in chunk.iter_mut().enumerate() {
+                    if levels[index % channel_count].is_some() {
+                        *value = sample(values[index]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Mosh<u8> for MoshCrush {
+    fn glitch(&self, chunk: &mut [u8]) {
+        self.crush(chunk, 8, |value| value as u8);
+    }
+}
+
+impl Mosh<u16> for MoshCrush {
+    fn glitch(&self, chunk: &mut [u16]) {
+        self.crush(chunk, 16, |value| value as u16);
+    }
+}
+
+fn hue(red: u8, green: u8, blue: u8) -> u8 {
+    let (red, green, blue) = (f64::from(red), f64::from(green), f64::from(blue));
+    let max = red.max(green).max(blue);
+    let delta = max - red.min(green).min(blue);
+
+    if delta == 0.0 {
+        return 0;
+    }
+
+    let sector = if max == red {
+        ((green - blue) / delta).rem_euclid(6.0)
+    } else if max == green {
+        (blue - red) / delta + 2.0
+    } else {
+        (red - green) / delta + 4.0
+    };
+
+    (sector / 6.0 * 255.0) as u8
+}
+
+impl<T: Copy + Default> Mosh<T> for MoshSplit {
+    fn glitch(&self, chunk: &mut [T]) {
+        let channel_count = cmp::max(self.channel_count, 1);
+        let width = (self.line_size / channel_count) as i64;
+        let height = (chunk.len() / cmp::max(self.line_size, 1)) as i64;
+
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let source = chunk.to_vec();
+        let alpha = matches!(channel_count, 2 | 4).then_some(channel_count - 1);
+        let index = |x: i64, y: i64| y as usize * self.line_size + x as usize * channel_count;
+
+        for (channel, (offset_x, offset_y)) in self.offsets.iter().enumerate().take(channel_count) {
+            for y in 0..height {
+                for x in 0..width {
+                    let source_x = x - i64::from(*offset_x);
+                    let source_y = y - i64::from(*offset_y);
+                    let position = match self.edge {
+                        MoshEdge::Clamp => {
+                            Some((source_x.clamp(0, width - 1), source_y.clamp(0, height - 1)))
+                        }
+                        MoshEdge::Wrap => {
+                            Some((source_x.rem_euclid(width), source_y.rem_euclid(height)))
+                        }
+                        MoshEdge::Transparent => ((0..width).contains(&source_x)
+                            && (0..height).contains(&source_y))
+                        .then_some((source_x, source_y)),
+                    };
+
+                    let pixel = index(x, y);
+
+                    if let Some((source_x, source_y)) = position {
+                        chunk[pixel + channel] = source[index(source_x, source_y) + channel];
+                    } else {
+                        chunk[pixel + channel] = T::default();
+
+                        if let Some(alpha) = alpha {
+                            chunk[pixel + alpha] = T::default();
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl MoshWave {
+    fn offset(&self, line: usize) -> i64 {
+        let period = cmp::max(self.period, 1);
+        let position = line as u128 + self.phase as u128;
+        let cycle = (position % period as u128) as f64 / period as f64;
+        let amplitude = self.amplitude as f64;
+
+        let offset = match self.shape {
+            MoshWaveShape::Sine => amplitude * (cycle * TAU).sin(),
+            MoshWaveShape::Triangle => amplitude * (1.0 - 4.0 * ((cycle + 0.25) % 1.0 - 0.5).abs()),
+            MoshWaveShape::Noise if self.noise.is_empty() => 0.0,
+            MoshWaveShape::Noise => {
+                let point = |index: u128| {
+                    f64::from(self.noise[(index % self.noise.len() as u128) as usize])
+                };
+                let from = point(position / period as u128);
+                let to = point(position / period as u128 + 1);
+                let step = cycle * cycle * (3.0 - 2.0 * cycle);
+
+                from + (to - from) * step
+            }
+        };
+
+        offset.round() as i64
+    }
+}
+
+impl<T> Mosh<T> for MoshWave {
+    fn glitch(&self, chunk: &mut [T]) {
+        let channel_count = cmp::max(self.channel_count, 1);
+        let line_size = self.line_size - self.line_size % channel_count;
+
+        if line_size == 0 {
+            return;
+        }
+
+        let width = (line_size / channel_count) as i64;
+
+        for (index, line) in chunk.chunks_exact_mut(self.line_size).enumerate() {
+            let offset = self.offset(index).rem_euclid(width) as usize;
+
+            line[..line_size].rotate_right(offset * channel_count);
+        }
+    }
+}
+
+impl<T: Copy> Mosh<T> for MoshBlocks {
+    fn glitch(&self, chunk: &mut [T]) {
+        let channel_count = cmp::max(self.channel_count, 1);
+        let line_size = self.line_size;
+        let width = line_size / channel_count;
+        let height = chunk.len() / cmp::max(line_size, 1);
+        let size = self.size;
+
+        if size == 0 || width < size || height < size {
+            return;
+        }
+
+        let (columns, rows) = (width / size, height / size);
+        let block_size = size * channel_count;
+
+        // First sample of a block, out of range blocks are clamped
+        let origin = |(column, row): (usize, usize)| {
+            cmp::min(row, rows - 1) * size * line_size + cmp::min(column, columns - 1) * block_size
+        };
+
+        for op in &self.ops {
+            match op {
+                MoshBlockOp::Displace(block, x, y) => {
+                    let start = origin(*block);
+                    let (first_x, first_y) =
+                        ((start % line_size) / channel_count, start / line_size);
+                    let pixels: Vec<T> = (0..size)
+                        .flat_map(|line| (0..size).map(move |pixel| (pixel, line)))
+                        .flat_map(|(pixel, line)| {
+                            let source_x = (first_x + pixel) as i64 + i64::from(*x);
+                            let source_y = (first_y + line) as i64 + i64::from(*y);
+                            let source_x = source_x.clamp(0, width as i64 - 1) as usize;
+                            let source_y = source_y.clamp(0, height as i64 - 1) as usize;
+                            let source = source_y * line_size + source_x * channel_count;
+
+                            chunk[source..source + channel_count].to_vec()
+                        })
+                        .collect();
+
+                    for (line, pixels) in pixels.chunks_exact(block_size).enumerate() {
+                        let start = start + line * line_size;
+                        chunk[start..start + block_size].copy_from_slice(pixels);
+                    }
+                }
+
+                MoshBlockOp::Duplicate(from, to) => {
+                    let (from, to) = (origin(*from), origin(*to));
+
+                    for line in 0..size {
+                        let offset = line * line_size;
+                        chunk.copy_within(from + offset..from + offset + block_size, to + offset);
+                    }
+                }
+
+                MoshBlockOp::Swap(first, second) => {
+                    let (first, second) = (origin(*first), origin(*second));
+
+                    if first != second {
+                        for line in 0..size {
+                            let (first, second) =
+                                (first + line * line_size, second + line * line_size);
+                            let block = chunk[first..first + block_size].to_vec();
+
+                            chunk.copy_within(second..second + block_size, first);
+                            chunk[second..second + block_size].copy_from_slice(&block);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: Copy> Mosh<T> for MoshSmear {
+    fn glitch(&self, chunk: &mut [T]) {
+        let channel_count = cmp::max(self.channel_count, 1);
+        let width = self.line_size / channel_count;
+        let height = chunk.len() / cmp::max(self.line_size, 1);
+
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let span = |count: usize| {
+            self.span.map_or((0, count), |(first, last)| {
+                let last = cmp::min(last, count);
+                (cmp::min(first, last), last)
+            })
+        };
+
+        if self.vertical {
+            let source = cmp::min(self.source, height - 1);
+            let (first, last) = span(width);
+            let start = source * self.line_size + first * channel_count;
+            let end = source * self.line_size + last * channel_count;
+
+            for line in source + 1..=cmp::min(source.saturating_add(self.length), height - 1) {
+                chunk.copy_within(start..end, line * self.line_size + first * channel_count);
+            }
+        } else {
+            let source = cmp::min(self.source, width - 1);
+            let (first, last) = span(height);
+            let pixel = source * channel_count..(source + 1) * channel_count;
+
+            for line in chunk
+                .chunks_exact_mut(self.line_size)
+                .take(last)
+                .skip(first)
+            {
+                for target in source + 1..=cmp::min(source.saturating_add(self.length), width - 1) {
+                    line.copy_within(pixel.clone(), target * channel_count);
+                }
+            }
+        }
+    }
+}
+
+impl Mosh for MoshFilterOp {
+    fn glitch(&self, data: &mut [u8]) {
+        let data_length = data.len();
+
+        match self {
+            Self::Graffiti(position, bytes) => {
+                let start = cmp::min(*position, data_length);
+                let end = cmp::min(start + bytes.len(), data_length);
+
+                data[start..end].copy_from_slice(&bytes[..end - start]);
+            }
+
+            Self::Replace(position, length, from, to) => {
+                let start = cmp::min(*position, data_length);
+                let end = cmp::min(start.saturating_add(*length), data_length);
+
+                for byte in &mut data[start..end] {
+                    if byte == from {
+                        *byte = *to;
+                    }
+                }
+            }
+
+            Self::Transpose(first, second, length) => {
+                let length = cmp::min(
+                    *length,
+                    data_length.saturating_sub(cmp::max(*first, *second)),
+                );
+
+                if length > 0 {
+                    let block = data[*first..first + length].to_vec();
+
+                    data.copy_within(*second..second + length, *first);
+                    data[*second..second + length].copy_from_slice(&block);
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,13 @@
 use glib::prelude::Cast;
 use gtk::{gdk, glib};
-use png::ColorType;
+use png::{BitDepth, ColorType};
 
 use std::path::{Path, PathBuf};
 
 use libmosh::{
     err::MoshError,
     ops::{read_file, write_file},
-    MoshCore, MoshOptions,
+    MoshBlend, MoshCore, MoshOptions, MoshWaveShape,
 };
 
 pub struct Image {
@@ -35,12 +35,37 @@ impl Image {
     }
 
     fn generate_texture(
-        buf: &Vec<u8>,
+        buf: &[u8],
         width: u32,
         height: u32,
         color_type: ColorType,
+        bit_depth: BitDepth,
         line_size: usize,
     ) -> gdk::MemoryTexture {
+        // Preview 16-bit images with their most significant bytes
+        // and scale packed samples up to 8 bits
+        let (buf, line_size): (Vec<u8>, usize) = match bit_depth {
+            BitDepth::Sixteen => (buf.iter().step_by(2).copied().collect(), line_size / 2),
+            BitDepth::Eight => (buf.to_vec(), line_size),
+            _ => {
+                let bits = bit_depth as usize;
+                let max = (1_u8 << bits) - 1;
+                let mut unpacked = Vec::with_capacity(width as usize * height as usize);
+
+                for row in buf.chunks_exact(line_size) {
+                    for x in 0..width as usize {
+                        let position = x * bits;
+                        let shift = 8 - bits - position % 8;
+                        let sample = (row[position / 8] >> shift) & max;
+
+                        unpacked.push(sample * (u8::MAX / max));
+                    }
+                }
+
+                (unpacked, width as usize)
+            }
+        };
+
         let (format, stride) = match &color_type {
             ColorType::Indexed => {
                 todo!()
@@ -93,7 +118,7 @@ impl Image {
             width as i32,
             height as i32,
             format,
-            &glib::Bytes::from(buf),
+            &glib::Bytes::from_owned(buf),
             stride as usize,
         )
     }
@@ -109,6 +134,7 @@ impl Image {
                 self.core.data.width,
                 self.core.data.height,
                 self.core.data.color_type,
+                self.core.data.bit_depth,
                 self.core.data.line_size,
             );
 
@@ -134,16 +160,19 @@ impl Image {
         Ok(())
     }
 
-    pub fn mosh_file(&mut self) {
-        self.core.mosh().unwrap();
+    pub fn mosh_file(&mut self) -> Result<(), MoshError> {
+        self.core.mosh()?;
         self.texture = Self::generate_texture(
             &self.core.data.buf,
             self.core.data.width,
             self.core.data.height,
             self.core.data.color_type,
+            self.core.data.bit_depth,
             self.core.data.line_size,
         )
         .upcast();
+
+        Ok(())
     }
 
     pub fn get_texture(&mut self) -> gdk::Texture {
@@ -196,6 +225,74 @@ impl Image {
         self.core.options.channel_shift = value;
     }
 
+    pub fn set_bit_flip(&mut self, value: f64) {
+        self.core.options.bit_flip = value;
+    }
+
+    pub fn set_bit_flip_intensity(&mut self, value: u16) {
+        self.core.options.bit_flip_intensity = value;
+    }
+
+    pub fn set_byte_replace(&mut self, value: f64) {
+        self.core.options.byte_replace = value;
+    }
+
+    pub fn set_byte_replace_intensity(&mut self, value: u16) {
+        self.core.options.byte_replace_intensity = value;
+    }
+
+    pub fn set_byte_insert(&mut self, value: f64) {
+        self.core.options.byte_insert = value;
+    }
+
+    pub fn set_byte_insert_intensity(&mut self, value: u16) {
+        self.core.options.byte_insert_intensity = value;
+    }
+
+    pub fn set_byte_delete(&mut self, value: f64) {
+        self.core.options.byte_delete = value;
+    }
+
+    pub fn set_byte_delete_intensity(&mut self, value: u16) {
+        self.core.options.byte_delete_intensity = value;
+    }
+
+    pub fn set_wave(&mut self, value: f64) {
+        self.core.options.wave = value;
+    }
+
+    pub fn set_wave_shape(&mut self, value: MoshWaveShape) {
+        self.core.options.wave_shape = value;
+    }
+
+    pub fn set_wave_amplitude(&mut self, value: u16) {
+        self.core.options.wave_amplitude = value;
+    }
+
+    pub fn set_wave_period(&mut self, value: u16) {
+        self.core.options.wave_period = value;
+    }
+
+    pub fn set_wave_phase(&mut self, value: u16) {
+        self.core.options.wave_phase = value;
+    }
+
+    pub fn set_mix(&mut self, value: f64) {
+        self.core.options.mix = value;
+    }
+
+    pub fn set_blend(&mut self, value: MoshBlend) {
+        self.core.options.blend = value;
+    }
+
+    pub fn set_preserve_alpha(&mut self, value: bool) {
+        self.core.options.preserve_alpha = value;
+    }
+
+    pub fn set_independent_streams(&mut self, value: bool) {
+        self.core.options.independent_streams = value;
+    }
+
     pub fn save_settings(&mut self) {
         self.settings = Some(self.core.options.clone());
     }
@@ -125,35 +125,45 @@ impl Window {
         let seed = buffer.text().to_string();
         let mut image = self.imp().image.borrow_mut();
 
-        if seed.parse::<u64>().is_err() {
+        let result = if seed.parse::<u64>().is_err() {
             image.new_seed();
             self.imp()
                 .seed
                 .buffer()
                 .set_text(image.get_seed().to_string());
 
-            image.mosh_file();
+            image.mosh_file()
         } else {
             image.set_seed(seed.parse::<u64>().unwrap());
-            image.mosh_file();
+            let result = image.mosh_file();
             buffer.set_text(image.get_seed().to_string());
-        }
+            result
+        };
 
         image.new_seed();
-        self.imp().picture.set_paintable(Some(&image.get_texture()));
+
+        if let Err(error) = result {
+            self.show_message(&error.to_string(), 3);
+        } else {
+            self.imp().picture.set_paintable(Some(&image.get_texture()));
+        }
     }
 
     fn mosh_rewind(&self) {
         let mut image = self.imp().image.borrow_mut();
 
         image.load_settings();
-        image.mosh_file();
+        let result = image.mosh_file();
         self.imp()
             .seed
             .buffer()
             .set_text(image.get_seed().to_string());
 
-        self.imp().picture.set_paintable(Some(&image.get_texture()));
+        if let Err(error) = result {
+            self.show_message(&error.to_string(), 3);
+        } else {
+            self.imp().picture.set_paintable(Some(&image.get_texture()));
+        }
     }
 
     fn mosh(&self) {
@@ -162,7 +172,12 @@ impl Window {
         if image.is_present {
             image.save_settings();
             image.new_seed();
-            image.mosh_file();
+
+            if let Err(error) = image.mosh_file() {
+                self.show_message(&error.to_string(), 3);
+                return;
+            }
+
             self.imp()
                 .seed
                 .buffer()
@@ -179,7 +194,11 @@ impl Window {
 
         if image.open_file(&file.path().unwrap()).is_ok() {
             if image.core.data.color_type != ColorType::Indexed {
-                image.mosh_file();
+                if let Err(error) = image.mosh_file() {
+                    self.show_message(&error.to_string(), 3);
+                    return;
+                }
+
                 self.imp().picture.set_paintable(Some(&image.get_texture()));
                 self.skip_placeholder();
             } else {
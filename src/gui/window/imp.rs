@@ -5,6 +5,8 @@ use png::ColorType;
 
 use std::cell::RefCell;
 
+use libmosh::{MoshBlend, MoshWaveShape};
+
 use crate::gui::window::Image;
 
 #[derive(CompositeTemplate)]
@@ -27,6 +29,30 @@ pub struct Window {
     #[template_child]
     pub btn_channel_shift: TemplateChild<SpinButton>,
     #[template_child]
+    pub btn_bit_flip: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_bit_flip_intensity: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_byte_replace: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_byte_replace_intensity: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_byte_insert: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_byte_insert_intensity: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_byte_delete: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_byte_delete_intensity: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_wave: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_wave_amplitude: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_wave_period: TemplateChild<SpinButton>,
+    #[template_child]
+    pub btn_wave_phase: TemplateChild<SpinButton>,
+    #[template_child]
     pub btn_rewind: TemplateChild<Button>,
     #[template_child]
     pub toast_overlay: TemplateChild<adw::ToastOverlay>,
@@ -85,6 +111,18 @@ impl ObjectSubclass for Window {
             btn_flip: TemplateChild::default(),
             btn_channel_swap: TemplateChild::default(),
             btn_channel_shift: TemplateChild::default(),
+            btn_bit_flip: TemplateChild::default(),
+            btn_bit_flip_intensity: TemplateChild::default(),
+            btn_byte_replace: TemplateChild::default(),
+            btn_byte_replace_intensity: TemplateChild::default(),
+            btn_byte_insert: TemplateChild::default(),
+            btn_byte_insert_intensity: TemplateChild::default(),
+            btn_byte_delete: TemplateChild::default(),
+            btn_byte_delete_intensity: TemplateChild::default(),
+            btn_wave: TemplateChild::default(),
+            btn_wave_amplitude: TemplateChild::default(),
+            btn_wave_period: TemplateChild::default(),
+            btn_wave_phase: TemplateChild::default(),
             btn_rewind: TemplateChild::default(),
             toast_overlay: TemplateChild::default(),
             dialog_open,
@@ -219,6 +257,122 @@ impl Window {
     fn handle_channel_shift(&self, button: &gtk::SpinButton) {
         self.image.borrow_mut().set_channel_shift(button.value());
     }
+
+    #[template_callback]
+    fn handle_bit_flip(&self, button: &gtk::SpinButton) {
+        self.image.borrow_mut().set_bit_flip(button.value());
+    }
+
+    #[template_callback]
+    fn handle_bit_flip_intensity(&self, button: &gtk::SpinButton) {
+        self.image
+            .borrow_mut()
+            .set_bit_flip_intensity(button.value() as u16);
+    }
+
+    #[template_callback]
+    fn handle_byte_replace(&self, button: &gtk::SpinButton) {
+        self.image.borrow_mut().set_byte_replace(button.value());
+    }
+
+    #[template_callback]
+    fn handle_byte_replace_intensity(&self, button: &gtk::SpinButton) {
+        self.image
+            .borrow_mut()
+            .set_byte_replace_intensity(button.value() as u16);
+    }
+
+    #[template_callback]
+    fn handle_byte_insert(&self, button: &gtk::SpinButton) {
+        self.image.borrow_mut().set_byte_insert(button.value());
+    }
+
+    #[template_callback]
+    fn handle_byte_insert_intensity(&self, button: &gtk::SpinButton) {
+        self.image
+            .borrow_mut()
+            .set_byte_insert_intensity(button.value() as u16);
+    }
+
+    #[template_callback]
+    fn handle_byte_delete(&self, button: &gtk::SpinButton) {
+        self.image.borrow_mut().set_byte_delete(button.value());
+    }
+
+    #[template_callback]
+    fn handle_byte_delete_intensity(&self, button: &gtk::SpinButton) {
+        self.image
+            .borrow_mut()
+            .set_byte_delete_intensity(button.value() as u16);
+    }
+
+    #[template_callback]
+    fn handle_wave(&self, button: &gtk::SpinButton) {
+        self.image.borrow_mut().set_wave(button.value());
+    }
+
+    #[template_callback]
+    fn handle_wave_shape(&self, _pspec: &glib::ParamSpec, dropdown: &gtk::DropDown) {
+        let shape = match dropdown.selected() {
+            1 => MoshWaveShape::Triangle,
+            2 => MoshWaveShape::Noise,
+            _ => MoshWaveShape::Sine,
+        };
+
+        self.image.borrow_mut().set_wave_shape(shape);
+    }
+
+    #[template_callback]
+    fn handle_wave_amplitude(&self, button: &gtk::SpinButton) {
+        self.image
+            .borrow_mut()
+            .set_wave_amplitude(button.value() as u16);
+    }
+
+    #[template_callback]
+    fn handle_wave_period(&self, button: &gtk::SpinButton) {
+        self.image
+            .borrow_mut()
+            .set_wave_period(button.value() as u16);
+    }
+
+    #[template_callback]
+    fn handle_wave_phase(&self, button: &gtk::SpinButton) {
+        self.image
+            .borrow_mut()
+            .set_wave_phase(button.value() as u16);
+    }
+
+    #[template_callback]
+    fn handle_mix(&self, button: &gtk::SpinButton) {
+        self.image.borrow_mut().set_mix(button.value());
+    }
+
+    #[template_callback]
+    fn handle_blend(&self, _pspec: &glib::ParamSpec, dropdown: &gtk::DropDown) {
+        let blend = match dropdown.selected() {
+            1 => MoshBlend::Difference,
+            2 => MoshBlend::Screen,
+            3 => MoshBlend::Multiply,
+            _ => MoshBlend::Normal,
+        };
+
+        self.image.borrow_mut().set_blend(blend);
+    }
+
+    #[template_callback]
+    fn handle_preserve_alpha(&self, button: &gtk::CheckButton) {
+        self.image
+            .borrow_mut()
+            .set_preserve_alpha(button.is_active());
+    }
+
+    #[template_callback]
+    fn handle_independent_streams(&self, button: &gtk::CheckButton) {
+        self.image
+            .borrow_mut()
+            .set_independent_streams(button.is_active());
+    }
 }
 
 impl WidgetImpl for Window {}
@@ -41,27 +41,40 @@ write_file(
 #![allow(deprecated)]
 
 use fast_image_resize as fr;
-use png::{BitDepth, ColorType, Decoder};
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
 use rand::{
     distributions::{Distribution, Uniform},
-    RngCore, SeedableRng,
+    Rng, RngCore, SeedableRng,
 };
+use rand_chacha::ChaCha8Rng;
 
-use std::{cmp, num::NonZeroU32};
+use std::{cmp, fmt, num::NonZeroU32, str::FromStr};
 
 use crate::{
     err::MoshError,
-    fx::{Mosh, MoshChunk, MoshLine},
+    fx::{
+        Mosh, MoshBlockOp, MoshBlocks, MoshByte, MoshChunk, MoshColor, MoshCrush, MoshDither,
+        MoshEdge, MoshFilterOp, MoshLine, MoshRegion, MoshSkew, MoshSmear, MoshSort, MoshSortKey,
+        MoshSortRuns, MoshSplit, MoshWave,
+    },
+    pipeline::{MoshContext, MoshEffect, MoshLevel, MoshPipeline},
 };
 
+mod deflate;
 pub mod err;
+mod filter;
 pub mod fx;
 pub mod ops;
+pub mod pipeline;
+#[cfg(feature = "serde")]
+pub mod recipe;
 
 /// Image data.
 ///
 /// It holds the original image, buffer and parameters.
+/// Fields may be added in minor releases, start from [`MoshData::default`].
 #[derive(Clone)]
+#[non_exhaustive]
 pub struct MoshData {
     /// Buffer.
     pub buf: Vec<u8>,
@@ -77,17 +90,41 @@ pub struct MoshData {
     pub bit_depth: BitDepth,
     /// Line size.
     pub line_size: usize,
+    /// Palette buffer.
+    pub palette_buf: Vec<u8>,
+    /// Original palette (`PLTE`), empty unless the image is [`Indexed`].
+    ///
+    /// [`Indexed`]: ColorType::Indexed
+    pub palette: Vec<u8>,
+    /// Palette transparency buffer, entries follow their palette entries.
+    pub trns_buf: Option<Vec<u8>>,
+    /// Original palette transparency (`tRNS`).
+    pub trns: Option<Vec<u8>>,
 }
 
 /// Processing options.
 ///
 /// Minimal `pixelation` value is `1` (OFF).
+/// Fields may be added in minor releases, start from [`MoshOptions::default`].
 #[derive(Clone, Debug)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct MoshOptions {
     /// Minimal amount of chunks to process.
     pub min_rate: u16,
     /// Maximal amount of chunks to process.
     pub max_rate: u16,
+    /// Minimal chunk height, `0` when unset.
+    pub min_chunk_height: Option<MoshSize>,
+    /// Maximal chunk height, half the image height when unset.
+    pub max_chunk_height: Option<MoshSize>,
+    /// Minimal chunk width, `1` when unset.
+    ///
+    /// Chunks span whole lines unless either width is set.
+    pub min_chunk_width: Option<MoshSize>,
+    /// Maximal chunk width, the image width when unset.
+    pub max_chunk_width: Option<MoshSize>,
     /// Pixelation's intensity.
     pub pixelation: u8,
     /// Chance of line shift.
@@ -100,8 +137,424 @@ pub struct MoshOptions {
     pub channel_swap: f64,
     /// Chance of channel shift.
     pub channel_shift: f64,
+    /// Line and channel shift granularity.
+    pub shift_unit: MoshShiftUnit,
+    /// Color space of channel shifts and swaps, for RGB and RGBA images.
+    pub color_space: MoshColorSpace,
+    /// Chance of bit flips.
+    pub bit_flip: f64,
+    /// Bits flipped per chunk.
+    pub bit_flip_intensity: u16,
+    /// Chance of byte replacement.
+    pub byte_replace: f64,
+    /// Bytes replaced per chunk.
+    pub byte_replace_intensity: u16,
+    /// Chance of byte insertion.
+    pub byte_insert: f64,
+    /// Bytes inserted per chunk, the rest of the chunk drifts forward.
+    pub byte_insert_intensity: u16,
+    /// Chance of byte deletion.
+    pub byte_delete: f64,
+    /// Bytes deleted per chunk, the rest of the chunk drifts back.
+    pub byte_delete_intensity: u16,
+    /// Chance of pixel sorting.
+    pub sort: f64,
+    /// Pixel sorting key.
+    pub sort_key: MoshSortKey,
+    /// Pixel runs to sort.
+    pub sort_runs: MoshSortMode,
+    /// Lowest key of threshold runs, within `0..=255`.
+    pub sort_min: u8,
+    /// Highest key of threshold runs, within `0..=255`.
+    pub sort_max: u8,
+    /// Longest interval run, in pixels.
+    pub sort_interval: u16,
+    /// Chance of RGB split.
+    pub rgb_split: f64,
+    /// Largest RGB split offset, in pixels.
+    pub rgb_split_range: u16,
+    /// Fixed RGB split offsets per color channel, sampled from `rgb_split_range` when empty.
+    pub rgb_split_offsets: Vec<(i32, i32)>,
+    /// Edge handling of RGB split.
+    pub rgb_split_edge: MoshEdge,
+    /// Chance of wave displacement.
+    pub wave: f64,
+    /// Wave shape.
+    pub wave_shape: MoshWaveShape,
+    /// Largest wave offset, in pixels.
+    pub wave_amplitude: u16,
+    /// Wave length (inverse frequency), in lines.
+    pub wave_period: u16,
+    /// Lines the wave starts at.
+    pub wave_phase: u16,
+    /// Chance of macroblock mutations.
+    pub blocks: f64,
+    /// Macroblock size, in pixels.
+    pub block_size: u16,
+    /// Chance of mutating each macroblock.
+    pub block_displacement: f64,
+    /// Chance of line smear.
+    pub smear: f64,
+    /// Longest smear streak, in lines or pixels.
+    pub smear_length: u16,
+    /// Direction of smear streaks.
+    pub smear_direction: MoshOrientation,
+    /// Smears a random span of pixels (or lines) only.
+    pub smear_partial: bool,
+    /// Chance of copying a chunk to another line.
+    pub chunk_copy: f64,
+    /// Chance of swapping a chunk with another region.
+    pub chunk_swap: f64,
+    /// Chance of repeating a chunk below itself.
+    pub chunk_tile: f64,
+    /// Most chunk repeats.
+    pub chunk_tile_count: u16,
+    /// Chance of bit depth reduction (bitcrush).
+    pub crush: f64,
+    /// Kept bits per channel, the last value applies to the remaining channels.
+    pub crush_bits: Vec<u8>,
+    /// Dithering of crushed samples.
+    pub crush_dither: MoshDither,
+    /// Crushes the whole image once, instead of each chunk.
+    pub crush_global: bool,
+    /// Chance of stride skew.
+    pub skew: f64,
+    /// Largest stride error, in samples.
+    pub skew_range: u16,
+    /// Direction skewed lines drift in.
+    pub skew_direction: MoshSkewDirection,
+    /// Corruptions of the filtered PNG stream, `0` disables filter glitching.
+    ///
+    /// [`Indexed`] images wrap indices around the palette.
+    ///
+    /// [`Indexed`]: ColorType::Indexed
+    pub filter_glitch: u16,
+    /// Filter rows are encoded with, picked for each row when unset.
+    pub filter: Option<MoshFilterType>,
+    /// Chance of decoding a row with a random (wrong) filter.
+    pub filter_mismatch: f64,
+    /// Corrupted bytes of the compressed (IDAT) stream, `0` disables it.
+    ///
+    /// Rows that can not be decoded are restored from the original image,
+    /// [`Indexed`] images wrap indices around the palette.
+    ///
+    /// [`Indexed`]: ColorType::Indexed
+    pub deflate_glitch: u16,
     /// Random seed.
+    #[cfg_attr(feature = "serde", serde(with = "recipe::seed"))]
     pub seed: u64,
+    /// Keeps the alpha channel intact, only color channels are glitched and pixelated.
+    pub preserve_alpha: bool,
+    /// Opacity of the result over the original image, within `0..=1`.
+    pub mix: f64,
+    /// Blend mode of the result over the original image.
+    pub blend: MoshBlend,
+    /// Direction effects run in.
+    ///
+    /// Chunk heights and widths are measured along and across it.
+    pub orientation: MoshOrientation,
+    /// Draws chunk placement and each effect from its own random stream.
+    ///
+    /// Changing one chance then leaves the other effects intact.
+    pub independent_streams: bool,
+    /// [`Indexed`] image handling, applied by [`MoshCore::read_image`].
+    ///
+    /// [`Indexed`]: ColorType::Indexed
+    pub indexed: IndexedMode,
+}
+
+/// Processing modes for [`Indexed`] images.
+///
+/// [`Indexed`]: ColorType::Indexed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum IndexedMode {
+    /// Expands the palette to RGB/RGBA before processing.
+    #[default]
+    Expand,
+    /// Glitches the index stream, indices wrap around the palette.
+    Indices,
+    /// Glitches the palette.
+    Palette,
+    /// Glitches both the index stream and the palette.
+    Both,
+}
+
+/// Effect directions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshOrientation {
+    /// Effects run along rows.
+    #[default]
+    Horizontal,
+    /// Effects run along columns.
+    Vertical,
+    /// Picks either direction for each chunk.
+    Random,
+}
+
+/// Blend modes.
+///
+/// Alpha is always mixed normally. [`Indexed`] images blend their palette,
+/// pixels pick either index with ordered dithering.
+///
+/// [`Indexed`]: ColorType::Indexed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshBlend {
+    /// Result replaces the original.
+    #[default]
+    Normal,
+    /// Absolute difference.
+    Difference,
+    /// Inverted product of inverted values, lightens.
+    Screen,
+    /// Product, darkens.
+    Multiply,
+}
+
+/// PNG scanline filters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshFilterType {
+    None,
+    Sub,
+    Up,
+    Average,
+    Paeth,
+}
+
+/// Shift granularities.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshShiftUnit {
+    /// Shifts by any amount of samples, mixing channels of neighboring pixels.
+    #[default]
+    Sample,
+    /// Shifts by whole pixels.
+    Pixel,
+}
+
+/// Pixel sorting runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshSortMode {
+    /// Sorts runs of pixels with keys between `sort_min` and `sort_max`.
+    #[default]
+    Threshold,
+    /// Sorts runs of random lengths, up to `sort_interval`.
+    Interval,
+}
+
+/// Stride skew directions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshSkewDirection {
+    /// Lines drift left, as if they were longer.
+    Left,
+    /// Lines drift right, as if they were shorter.
+    Right,
+    /// Either, per chunk.
+    #[default]
+    Random,
+}
+
+/// Color spaces channel effects run in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshColorSpace {
+    /// Red, green and blue.
+    #[default]
+    Rgb,
+    /// Luma and chroma planes.
+    #[cfg_attr(feature = "cli", value(name = "ycbcr"))]
+    YCbCr,
+    /// Hue, saturation and value.
+    Hsv,
+}
+
+/// Wave shapes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MoshWaveShape {
+    /// Sine wave.
+    #[default]
+    Sine,
+    /// Triangle wave.
+    Triangle,
+    /// Smooth random offsets.
+    Noise,
+}
+
+/// Length in pixels or as a fraction of an image dimension.
+///
+/// Parsed from `"40"` (pixels) or `"25%"` (fraction).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
+pub enum MoshSize {
+    /// Pixels.
+    Pixels(u32),
+    /// Fraction, within `0..=1`.
+    Fraction(f64),
+}
+
+/// Processing plan.
+///
+/// Describes every mutation [`MoshCore::mosh`] performs, so it can be inspected,
+/// edited and replayed with [`MoshCore::apply`]. Positions and amounts are in samples.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct MoshPlan {
+    /// Chunks to process, in order.
+    pub chunks: Vec<MoshChunkPlan>,
+    /// Palette mutations, the palette is processed as a single line of RGB entries.
+    pub palette: Option<MoshChunkPlan>,
+    /// Filtered stream mutations, applied after pixelation.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub filter: Option<MoshFilterPlan>,
+    /// Compressed stream corruption, applied after filter glitches.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub deflate: Option<MoshDeflatePlan>,
+    /// Pixelation's intensity.
+    pub pixelation: u8,
+    /// Keeps the alpha channel intact, channels then exclude alpha.
+    pub preserve_alpha: bool,
+    /// Opacity of the result over the original image.
+    pub mix: f64,
+    /// Blend mode of the result over the original image.
+    pub blend: MoshBlend,
+}
+
+/// Planned filter glitch.
+///
+/// Each row is filtered with its `encode` filter, corrupted and reconstructed with
+/// its `decode` filter. Filters cycle if there are fewer of them than rows.
+/// Positions and lengths are in bytes of the filtered rows, without filter type bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshFilterPlan {
+    /// Encoding filters, per row.
+    pub encode: Vec<MoshFilterType>,
+    /// Decoding filters, per row.
+    pub decode: Vec<MoshFilterType>,
+    /// Mutations, in order.
+    pub ops: Vec<MoshFilterOp>,
+}
+
+/// Planned compressed stream corruption.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshDeflatePlan {
+    /// Overwritten bytes, as positions within `0..1` of the stream and new values.
+    ///
+    /// Positions are relative since the stream size is only known once compressed.
+    pub ops: Vec<(f64, u8)>,
+}
+
+/// Planned chunk.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoshChunkPlan {
+    /// First line.
+    pub first_line: usize,
+    /// Last line (exclusive).
+    pub last_line: usize,
+    /// First and last (exclusive) sample within a line, whole lines when `None`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub columns: Option<(usize, usize)>,
+    /// Runs effects along columns, lines and columns are then swapped.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub vertical: bool,
+    /// Color space channel shifts and swaps run in, converted back to RGB after each.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub color: Option<MoshColor>,
+    /// Mutations applied to each line, in order.
+    pub lines: Vec<MoshLine>,
+    /// Mutations applied to the whole chunk, in order.
+    pub chunk: Vec<MoshChunk>,
+    /// Pixel sorting, applied to each line after `chunk`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub sort: Option<MoshSort>,
+    /// RGB split, applied to the whole chunk after `sort`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub split: Option<MoshSplit>,
+    /// Wave displacement, applied to the whole chunk after `split`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub wave: Option<MoshWave>,
+    /// Macroblock mutations, applied to the whole chunk after `wave`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub blocks: Option<MoshBlocks>,
+    /// Line smear, applied to the whole chunk after `blocks`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub smear: Option<MoshSmear>,
+    /// Bitcrush, applied to the whole chunk after `smear`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub crush: Option<MoshCrush>,
+    /// Relocation within the image, applied after `crush`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub region: Option<MoshRegion>,
+    /// Stride skew, applied to the whole chunk after `region`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub skew: Option<MoshSkew>,
+    /// Byte mutations applied to the whole chunk after `skew`, in order.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub bytes: Vec<MoshByte>,
 }
 
 /// Core container.
@@ -130,11 +583,32 @@ impl MoshCore {
     ///
     /// It may fail if an image is not a valid PNG file.
     pub fn read_image(&mut self, input: &[u8]) -> Result<(), MoshError> {
-        let decoder = Decoder::new(input);
+        let mut decoder = Decoder::new(input);
+
+        if decoder.read_header_info()?.color_type == ColorType::Indexed
+            && self.options.indexed == IndexedMode::Expand
+        {
+            decoder.set_transformations(Transformations::EXPAND);
+        }
+
         let mut reader = decoder.read_info()?;
         let mut buf = vec![0_u8; reader.output_buffer_size()];
         let info = reader.next_frame(&mut buf)?;
 
+        if info.color_type == ColorType::Indexed {
+            let palette = reader.info().palette.as_deref().unwrap_or_default();
+
+            self.data.palette_buf = palette.to_vec();
+            self.data.palette = palette.to_vec();
+            self.data.trns = reader.info().trns.as_deref().map(<[u8]>::to_vec);
+            self.data.trns_buf.clone_from(&self.data.trns);
+        } else {
+            self.data.palette_buf.clear();
+            self.data.palette.clear();
+            self.data.trns_buf = None;
+            self.data.trns = None;
+        }
+
         self.data.buf = buf.clone();
         self.data.image = buf;
         self.data.width = info.width;
@@ -154,7 +628,9 @@ impl MoshCore {
 
     # Errors
 
-    * [`UnsupportedColorType`]: [`Indexed`] is not supported.
+    * [`UnsupportedColorType`]: [`Indexed`] image was not expanded on read.
+    * [`InvalidParameters`]: an option is out of range, see [`MoshOptions::validate`].
+      [`pixelation`] can not exceed image width or height.
 
     [`Indexed`]: ColorType::Indexed
 
@@ -194,12 +670,146 @@ impl MoshCore {
     ```
 
     [`UnsupportedColorType`]: crate::err::MoshError::UnsupportedColorType
+    [`InvalidParameters`]: crate::err::MoshError::InvalidParameters
+    [`pixelation`]: MoshOptions::pixelation
     */
     pub fn mosh(&mut self) -> Result<(), MoshError> {
         self.data.mosh(&self.options)?;
 
         Ok(())
     }
+
+    /// Plans processing of the current image with current [settings].
+    ///
+    /// # Errors
+    ///
+    /// * [`UnsupportedColorType`]: [`Indexed`] image was not expanded on read.
+    /// * [`InvalidParameters`]: an option is out of range, see [`MoshOptions::validate`].
+    ///
+    /// [settings]: MoshOptions
+    /// [`Indexed`]: ColorType::Indexed
+    /// [`UnsupportedColorType`]: crate::err::MoshError::UnsupportedColorType
+    /// [`InvalidParameters`]: crate::err::MoshError::InvalidParameters
+    pub fn plan(&self) -> Result<MoshPlan, MoshError> {
+        self.data.plan(&self.options)
+    }
+
+    /**
+    Applies a [plan] to the current image, storing the result in a [buffer].
+
+    Chunks exceeding the image are clipped and shifts wrap around the line,
+    so line and chunk mutations and pixel sorting can be replayed on images of any size.
+    Effects sized when planned keep the line size, channel count and bit depth
+    of the planned image, they only replay on chunks of the same geometry.
+
+    [buffer]: MoshData::buf
+    [plan]: MoshPlan
+
+    # Errors
+
+    * [`InvalidParameters`]: [`pixelation`] exceeds image width or height,
+      a channel is out of range, or an effect does not match its chunk geometry.
+
+    # Example
+    ```rust
+    use libmosh::{err::MoshError, fx::MoshLine, ops::read_file, MoshCore};
+
+    let input = read_file("src/util/test-rgb.png")?;
+    let mut image = MoshCore::new();
+
+    image.options.seed = 42;
+    image.read_image(&input)?;
+
+    let mut plan = image.plan()?;
+
+    for chunk in &mut plan.chunks {
+        chunk.lines.push(MoshLine::Reverse);
+    }
+
+    image.apply(&plan)?;
+    # Ok::<(), MoshError>(())
+    ```
+
+    [`InvalidParameters`]: crate::err::MoshError::InvalidParameters
+    [`pixelation`]: MoshPlan::pixelation
+    */
+    pub fn apply(&mut self, plan: &MoshPlan) -> Result<(), MoshError> {
+        self.data.apply(plan)
+    }
+
+    /// Processes an image with an effect [pipeline] instead of built-in effects.
+    ///
+    /// Chunk placement, palette and pixelation follow current [settings].
+    /// Each stage draws from its own random stream when [`independent_streams`] is set.
+    ///
+    /// # Errors
+    ///
+    /// * [`UnsupportedColorType`]: [`Indexed`] image was not expanded on read.
+    /// * [`InvalidParameters`]: an option or a stage chance is out of range.
+    ///   [`pixelation`] can not exceed image width or height.
+    ///
+    /// [pipeline]: MoshPipeline
+    /// [settings]: MoshOptions
+    /// [`independent_streams`]: MoshOptions::independent_streams
+    /// [`Indexed`]: ColorType::Indexed
+    /// [`UnsupportedColorType`]: crate::err::MoshError::UnsupportedColorType
+    /// [`InvalidParameters`]: crate::err::MoshError::InvalidParameters
+    /// [`pixelation`]: MoshOptions::pixelation
+    pub fn mosh_with(&mut self, pipeline: &MoshPipeline) -> Result<(), MoshError> {
+        self.data.mosh_with(&self.options, pipeline)
+    }
+}
+
+impl MoshPlan {
+    /// Checks that all channels are within their channel count and `mix` is within `0..=1`.
+    ///
+    /// # Errors
+    ///
+    /// * [`InvalidParameters`]: names the first value out of range.
+    ///
+    /// [`InvalidParameters`]: crate::err::MoshError::InvalidParameters
+    pub fn validate(&self) -> Result<(), MoshError> {
+        if !(0.0..=1.0).contains(&self.mix) {
+            return Err(MoshError::InvalidParameters {
+                field: "mix",
+                range: String::from("0..=1"),
+            });
+        }
+
+        for chunk in self.chunks.iter().chain(&self.palette) {
+            let line_channels = chunk.lines.iter().filter_map(|line| match line {
+                MoshLine::ChannelShift(_, channel, channel_count) => {
+                    Some((*channel, *channel, *channel_count))
+                }
+                _ => None,
+            });
+
+            let chunk_channels = chunk.chunk.iter().filter_map(|chunk| match chunk {
+                MoshChunk::ChannelSwap(channel_1, channel_2, channel_count) => {
+                    Some((*channel_1, *channel_2, *channel_count))
+                }
+                MoshChunk::Flip => None,
+            });
+
+            let sort_channels = chunk.sort.iter().filter_map(|sort| match sort.key {
+                MoshSortKey::Channel(channel) => Some((channel, channel, sort.channel_count)),
+                _ => None,
+            });
+
+            for (channel_1, channel_2, channel_count) in
+                line_channels.chain(chunk_channels).chain(sort_channels)
+            {
+                if channel_1 >= channel_count || channel_2 >= channel_count {
+                    return Err(MoshError::InvalidParameters {
+                        field: "channel",
+                        range: format!("0..{channel_count}"),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl MoshOptions {
@@ -215,6 +825,147 @@ impl MoshOptions {
     pub fn new_seed(&mut self) {
         self.seed = Self::generate_seed();
     }
+
+    /// Checks that all chances and `mix` are within `0..=1`.
+    ///
+    /// # Errors
+    ///
+    /// * [`InvalidParameters`]: names the first option out of range.
+    ///
+    /// [`InvalidParameters`]: crate::err::MoshError::InvalidParameters
+    pub fn validate(&self) -> Result<(), MoshError> {
+        let chances = [
+            ("line_shift", self.line_shift),
+            ("reverse", self.reverse),
+            ("flip", self.flip),
+            ("channel_swap", self.channel_swap),
+            ("channel_shift", self.channel_shift),
+            ("bit_flip", self.bit_flip),
+            ("byte_replace", self.byte_replace),
+            ("byte_insert", self.byte_insert),
+            ("byte_delete", self.byte_delete),
+            ("sort", self.sort),
+            ("rgb_split", self.rgb_split),
+            ("wave", self.wave),
+            ("blocks", self.blocks),
+            ("block_displacement", self.block_displacement),
+            ("smear", self.smear),
+            ("chunk_copy", self.chunk_copy),
+            ("chunk_swap", self.chunk_swap),
+            ("chunk_tile", self.chunk_tile),
+            ("crush", self.crush),
+            ("skew", self.skew),
+            ("filter_mismatch", self.filter_mismatch),
+            ("mix", self.mix),
+        ];
+
+        for (field, value) in chances {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(MoshError::InvalidParameters {
+                    field,
+                    range: String::from("0..=1"),
+                });
+            }
+        }
+
+        let sizes = [
+            ("min_chunk_height", self.min_chunk_height),
+            ("max_chunk_height", self.max_chunk_height),
+            ("min_chunk_width", self.min_chunk_width),
+            ("max_chunk_width", self.max_chunk_width),
+        ];
+
+        for (field, size) in sizes {
+            if let Some(MoshSize::Fraction(value)) = size {
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(MoshError::InvalidParameters {
+                        field,
+                        range: String::from("0%..=100%"),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl MoshBlend {
+    // Blends `top` over `base`, with values in `0..=max`
+    fn apply(self, base: u32, top: u32, max: u32, mix: f64) -> u32 {
+        let blended = match self {
+            Self::Normal => top,
+            Self::Difference => base.abs_diff(top),
+            Self::Screen => max - (max - base) * (max - top) / max,
+            Self::Multiply => base * top / max,
+        };
+
+        (f64::from(base) + (f64::from(blended) - f64::from(base)) * mix).round() as u32
+    }
+}
+
+impl MoshShiftUnit {
+    // Rounds a shift amount (in samples) down to whole pixels if needed
+    pub(crate) fn align(self, amount: usize, channel_count: usize) -> usize {
+        match self {
+            Self::Sample => amount,
+            Self::Pixel => amount - amount % channel_count,
+        }
+    }
+}
+
+impl MoshSize {
+    fn resolve(self, length: usize) -> usize {
+        match self {
+            Self::Pixels(pixels) => pixels as usize,
+            Self::Fraction(fraction) => (fraction * length as f64).round() as usize,
+        }
+    }
+}
+
+impl FromStr for MoshSize {
+    type Err = MoshError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || MoshError::InvalidParameters {
+            field: "size",
+            range: String::from("pixels or 0%..=100%"),
+        };
+
+        match value.trim().strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|percent| (0.0..=100.0).contains(percent))
+                .map(|percent| Self::Fraction(percent / 100.0))
+                .ok_or_else(error),
+            None => value.trim().parse().map(Self::Pixels).map_err(|_| error()),
+        }
+    }
+}
+
+impl fmt::Display for MoshSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pixels(pixels) => write!(f, "{pixels}"),
+            Self::Fraction(fraction) => write!(f, "{}%", fraction * 100.0),
+        }
+    }
+}
+
+impl From<MoshSize> for String {
+    fn from(size: MoshSize) -> Self {
+        size.to_string()
+    }
+}
+
+impl TryFrom<String> for MoshSize {
+    type Error = MoshError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
 }
 
 impl MoshData {
@@ -224,6 +975,7 @@ impl MoshData {
         let mut reader = decoder.read_info()?;
         let mut buf = vec![0_u8; reader.output_buffer_size()];
         let info = reader.next_frame(&mut buf)?;
+        let palette = reader.info().palette.as_deref().unwrap_or_default();
 
         Ok(Self {
             buf: vec![0_u8],
@@ -233,175 +985,1798 @@ impl MoshData {
             color_type: info.color_type,
             bit_depth: info.bit_depth,
             line_size: info.line_size,
+            palette_buf: palette.to_vec(),
+            palette: palette.to_vec(),
+            trns_buf: reader.info().trns.as_deref().map(<[u8]>::to_vec),
+            trns: reader.info().trns.as_deref().map(<[u8]>::to_vec),
         })
     }
 
     #[deprecated(since = "3.1.0")]
     pub fn mosh(&mut self, options: &MoshOptions) -> Result<(), MoshError> {
-        self.buf.clone_from(&self.image);
+        let plan = self.plan(options)?;
+
+        self.apply(&plan)
+    }
+
+    fn plan(&self, options: &MoshOptions) -> Result<MoshPlan, MoshError> {
+        options.validate()?;
+
+        if self.color_type == ColorType::Indexed && options.indexed == IndexedMode::Expand {
+            return Err(MoshError::UnsupportedColorType);
+        }
 
         let min_rate = options.min_rate;
         let max_rate = cmp::max(options.min_rate, options.max_rate);
-        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(options.seed);
+        let mut rng = Streams::new(options.seed, options.independent_streams, 0);
         let chunk_count_distrib = Uniform::from(min_rate..=max_rate);
-        let mosh_rate = chunk_count_distrib.sample(&mut rng);
+        let mosh_rate = chunk_count_distrib.sample(rng.get(Stream::Rate));
+        let channel_count = self.effect_channels(options.preserve_alpha);
+        let chunk_count =
+            if self.color_type == ColorType::Indexed && options.indexed == IndexedMode::Palette {
+                0
+            } else {
+                mosh_rate
+            };
+
+        let mut chunks: Vec<_> = (0..chunk_count)
+            .map(|_| {
+                let vertical = self.plan_orientation(rng.get(Stream::Chunk), options);
+                let (line_count, line_size) = self.dimensions(vertical, channel_count);
+                let chunk = Self::plan_chunk(
+                    line_count,
+                    line_size,
+                    channel_count,
+                    self.bit_depth as u8,
+                    &mut rng,
+                    options,
+                );
+                let block_size = chunk
+                    .columns
+                    .map_or(line_size, |(first, last)| last - first);
+                let crush = (!options.crush_global)
+                    .then(|| {
+                        self.plan_crush(block_size, channel_count, rng.get(Stream::Crush), options)
+                    })
+                    .flatten();
+
+                MoshChunkPlan {
+                    vertical,
+                    crush,
+                    ..chunk
+                }
+            })
+            .collect();
+
+        // Global bitcrush runs once over the whole image, after the other chunks
+        if options.crush_global {
+            let (line_count, line_size) = self.dimensions(false, channel_count);
+            let crush = self.plan_crush(line_size, channel_count, rng.get(Stream::Crush), options);
 
-        for _ in 0..mosh_rate {
-            Self::chunkmosh(self, &mut rng, options);
+            chunks.extend(crush.map(|crush| MoshChunkPlan {
+                first_line: 0,
+                last_line: line_count,
+                columns: None,
+                vertical: false,
+                color: None,
+                lines: Vec::new(),
+                chunk: Vec::new(),
+                sort: None,
+                split: None,
+                wave: None,
+                blocks: None,
+                smear: None,
+                crush: Some(crush),
+                region: None,
+                skew: None,
+                bytes: Vec::new(),
+            }));
         }
 
-        match self.color_type {
-            ColorType::Indexed => {
-                return Err(MoshError::UnsupportedColorType);
-            }
-            ColorType::Grayscale => {
-                Self::pixelation(self, options, fr::PixelType::U8);
-            }
-            ColorType::GrayscaleAlpha => {
-                Self::pixelation(self, options, fr::PixelType::U8x2);
-            }
-            ColorType::Rgb => {
-                Self::pixelation(self, options, fr::PixelType::U8x3);
-            }
-            ColorType::Rgba => {
-                Self::pixelation(self, options, fr::PixelType::U8x4);
+        let palette = (self.color_type == ColorType::Indexed
+            && options.indexed != IndexedMode::Indices)
+            .then(|| Self::plan_palette(self.palette.len() / 3, rng.get(Stream::Palette), options))
+            .flatten();
+
+        let filter = (options.filter_glitch > 0).then(|| {
+            let data_size = self.line_size * self.height as usize;
+            Self::plan_filter(data_size, self.line_size, rng.get(Stream::Filter), options)
+        });
+
+        let deflate = (options.deflate_glitch > 0).then(|| {
+            let rng = rng.get(Stream::Deflate);
+
+            MoshDeflatePlan {
+                ops: (0..options.deflate_glitch)
+                    .map(|_| (rng.gen(), rng.gen()))
+                    .collect(),
             }
-        }
+        });
 
-        Ok(())
+        Ok(MoshPlan {
+            chunks,
+            palette,
+            filter,
+            deflate,
+            pixelation: options.pixelation,
+            preserve_alpha: options.preserve_alpha,
+            mix: options.mix,
+            blend: options.blend,
+        })
     }
 
-    fn pixelation(&mut self, options: &MoshOptions, pixel_type: fr::PixelType) {
-        if options.pixelation > 1 {
-            let width = NonZeroU32::new(self.width).unwrap();
-            let height = NonZeroU32::new(self.height).unwrap();
-            let src_image =
-                fr::Image::from_vec_u8(width, height, self.buf.clone(), pixel_type).unwrap();
+    fn mosh_with(
+        &mut self,
+        options: &MoshOptions,
+        pipeline: &MoshPipeline,
+    ) -> Result<(), MoshError> {
+        pipeline.validate()?;
 
-            let dest_width = NonZeroU32::new(self.width / u32::from(options.pixelation)).unwrap();
-            let dest_height = NonZeroU32::new(self.height / u32::from(options.pixelation)).unwrap();
-            let orig_width = NonZeroU32::new(self.width).unwrap();
-            let orig_height = NonZeroU32::new(self.height).unwrap();
+        let plan = MoshPlan {
+            chunks: Vec::new(),
+            ..self.plan(options)?
+        };
 
-            let mut dest_image = fr::Image::new(dest_width, dest_height, src_image.pixel_type());
-            let mut orig_image = fr::Image::new(orig_width, orig_height, src_image.pixel_type());
-            let mut dest_view = dest_image.view_mut();
-            let mut orig_view = orig_image.view_mut();
-            let mut resizer = fr::Resizer::new(fr::ResizeAlg::Nearest);
+        let mut rng = Streams::new(
+            options.seed,
+            options.independent_streams,
+            pipeline.stages.len(),
+        );
 
-            resizer.resize(&src_image.view(), &mut dest_view).unwrap();
-            resizer.resize(&dest_image.view(), &mut orig_view).unwrap();
+        let min_rate = options.min_rate;
+        let max_rate = cmp::max(options.min_rate, options.max_rate);
+        let mosh_rate = Uniform::from(min_rate..=max_rate).sample(rng.get(Stream::Rate));
+        let channel_count = self.effect_channels(options.preserve_alpha);
+        let chunk_count =
+            if self.color_type == ColorType::Indexed && options.indexed == IndexedMode::Palette {
+                0
+            } else {
+                mosh_rate
+            };
 
-            self.buf = orig_image.into_vec();
-        }
+        let chunks: Vec<_> = (0..chunk_count)
+            .map(|_| {
+                let vertical = self.plan_orientation(rng.get(Stream::Chunk), options);
+                let (line_count, line_size) = self.dimensions(vertical, channel_count);
+                let (first_line, last_line, columns) = Self::plan_geometry(
+                    line_count,
+                    line_size,
+                    channel_count,
+                    rng.get(Stream::Chunk),
+                    options,
+                );
+
+                let context = MoshContext {
+                    line_size: columns.map_or(line_size, |(first, last)| last - first),
+                    line_count: last_line - first_line,
+                    channel_count,
+                    depth: self.bit_depth as u8,
+                };
+
+                let effects = pipeline
+                    .stages
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, stage)| {
+                        let rng = rng.stage(index);
+
+                        rng.gen_bool(stage.chance)
+                            .then(|| (stage.level, (stage.sampler)(rng, &context)))
+                    })
+                    .collect();
+
+                PipelineChunk {
+                    first_line,
+                    last_line,
+                    columns,
+                    vertical,
+                    effects,
+                }
+            })
+            .collect();
+
+        self.process(&chunks, &plan)
     }
 
-    // Use pnglitch approach
-    //
-    // TODO
-    // Add more `rng` to `chunk_size`?
-    fn chunkmosh(&mut self, rng: &mut impl rand::Rng, options: &MoshOptions) {
-        let line_count = self.buf.len() / self.line_size;
-        let channel_count = match self.color_type {
-            ColorType::Grayscale | ColorType::Indexed => 1,
-            ColorType::GrayscaleAlpha => 2,
-            ColorType::Rgb => 3,
-            ColorType::Rgba => 4,
-        };
+    fn apply(&mut self, plan: &MoshPlan) -> Result<(), MoshError> {
+        plan.validate()?;
+        self.validate_geometry(plan)?;
 
-        let line_shift_distrib = Uniform::from(0..self.line_size);
-        let line_number_distrib = Uniform::from(0..line_count);
-        let channel_count_distrib = Uniform::from(0..channel_count);
+        self.process(&plan.chunks, plan)
+    }
 
-        let first_line = line_number_distrib.sample(rng);
-        let chunk_size = line_number_distrib.sample(rng) / 2;
-        let last_line = if (first_line + chunk_size) > line_count {
-            line_count
-        } else {
-            first_line + chunk_size
-        };
+    // Effects sized when planned must match the chunk they run on
+    fn validate_geometry(&self, plan: &MoshPlan) -> Result<(), MoshError> {
+        let channel_count = self.effect_channels(plan.preserve_alpha);
+        let depth = self.bit_depth as u8;
 
-        let reverse = rng.gen_bool(options.reverse);
-        let flip = rng.gen_bool(options.flip);
+        for chunk in &plan.chunks {
+            let (_, line_size) = self.dimensions(chunk.vertical, channel_count);
+            let block_size = chunk.columns.map_or(line_size, |(first, last)| {
+                let last = cmp::min(last, line_size);
+                last - cmp::min(first, last)
+            });
 
-        let line_shift = rng.gen_bool(options.line_shift).then(|| {
-            let line_shift_amount = line_shift_distrib.sample(rng);
-            MoshLine::Shift(line_shift_amount)
-        });
+            let sizes = [
+                chunk
+                    .split
+                    .as_ref()
+                    .map(|split| (split.line_size, split.channel_count)),
+                chunk
+                    .wave
+                    .as_ref()
+                    .map(|wave| (wave.line_size, wave.channel_count)),
+                chunk
+                    .blocks
+                    .as_ref()
+                    .map(|blocks| (blocks.line_size, blocks.channel_count)),
+                chunk
+                    .smear
+                    .as_ref()
+                    .map(|smear| (smear.line_size, smear.channel_count)),
+                chunk
+                    .crush
+                    .as_ref()
+                    .map(|crush| (crush.line_size, crush.channel_count)),
+                chunk
+                    .skew
+                    .as_ref()
+                    .map(|skew| (skew.line_size, channel_count)),
+                chunk.color.map(|color| (block_size, color.channel_count)),
+            ];
 
-        let channel_shift = rng.gen_bool(options.channel_shift).then(|| {
-            let amount = line_shift_distrib.sample(rng) / channel_count;
-            let channel = channel_count_distrib.sample(rng);
-            MoshLine::ChannelShift(amount, channel, channel_count)
-        });
+            for (size, channels) in sizes.into_iter().flatten() {
+                if size != block_size {
+                    return Err(MoshError::InvalidParameters {
+                        field: "line_size",
+                        range: block_size.to_string(),
+                    });
+                }
 
-        let channel_swap = rng.gen_bool(options.channel_swap).then(|| {
-            let channel_1 = channel_count_distrib.sample(rng);
-            let channel_2 = channel_count_distrib.sample(rng);
-            MoshChunk::ChannelSwap(channel_1, channel_2, channel_count)
-        });
+                if channels != channel_count {
+                    return Err(MoshError::InvalidParameters {
+                        field: "channel_count",
+                        range: channel_count.to_string(),
+                    });
+                }
+            }
+
+            if chunk
+                .crush
+                .as_ref()
+                .is_some_and(|crush| crush.depth != depth)
+            {
+                return Err(MoshError::InvalidParameters {
+                    field: "depth",
+                    range: depth.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Chunks are taken apart, the plan provides everything else
+    fn process(&mut self, chunks: &[impl Process], plan: &MoshPlan) -> Result<(), MoshError> {
+        let pixelation = plan.pixelation;
+        let max_pixelation = cmp::min(self.width, self.height);
+
+        if u32::from(pixelation) > max_pixelation {
+            return Err(MoshError::InvalidParameters {
+                field: "pixelation",
+                range: format!("0..={max_pixelation}"),
+            });
+        }
+
+        let pixel_type = self.pixel_type();
+        let alpha = plan.preserve_alpha && self.effect_channels(true) < self.channel_count();
+        let dimensions = (
+            self.width as usize,
+            self.height as usize,
+            self.channel_count(),
+        );
+
+        self.buf.clone_from(&self.image);
+        self.palette_buf.clone_from(&self.palette);
+        self.trns_buf.clone_from(&self.trns);
 
-        for line_number in first_line..last_line {
-            let line_start = line_number * self.line_size;
-            let line_end = line_start + self.line_size;
-            let line = &mut self.buf[line_start..line_end];
+        match self.bit_depth {
+            BitDepth::Sixteen => {
+                let mut samples = Self::read_samples(&self.buf);
+                Self::process_chunks(&mut samples, dimensions, alpha, chunks);
 
-            if let Some(do_channel_shift) = &channel_shift {
-                do_channel_shift.glitch(line);
+                // `fast_image_resize` expects native-endian samples
+                self.buf = samples.iter().flat_map(|s| s.to_ne_bytes()).collect();
+                Self::pixelation(self, pixelation, pixel_type);
+                self.buf = self
+                    .buf
+                    .chunks_exact(2)
+                    .flat_map(|s| u16::from_ne_bytes([s[0], s[1]]).to_be_bytes())
+                    .collect();
             }
+            BitDepth::Eight => {
+                Self::process_chunks(&mut self.buf, dimensions, alpha, chunks);
 
-            if let Some(do_line_shift) = &line_shift {
-                do_line_shift.glitch(line);
+                Self::pixelation(self, pixelation, pixel_type);
             }
-            if reverse {
-                MoshLine::Reverse.glitch(line);
+            _ => {
+                // One sample per byte, repacked once processed
+                self.buf = self.unpack_samples(&self.buf);
+                Self::process_chunks(&mut self.buf, dimensions, alpha, chunks);
+
+                Self::pixelation(self, pixelation, pixel_type);
+                self.buf = self.pack_samples(&self.buf);
             }
         }
 
-        let chunk_start = first_line * self.line_size;
-        let chunk_end = last_line * self.line_size;
-        let chunk = &mut self.buf[chunk_start..chunk_end];
+        // Chunk effects may write any value
+        self.wrap_indices();
 
-        if let Some(do_channel_swap) = channel_swap {
-            do_channel_swap.glitch(chunk);
-        };
+        if let Some(filter) = &plan.filter {
+            self.filter_glitch(filter);
+        }
 
-        if flip {
-            MoshChunk::Flip.glitch(chunk);
-        };
+        if let Some(deflate) = &plan.deflate {
+            self.deflate_glitch(deflate)?;
+        }
+
+        // Pixelation and stream glitches move alpha along with colors
+        if alpha && (pixelation > 1 || plan.filter.is_some() || plan.deflate.is_some()) {
+            self.restore_alpha();
+        }
+
+        if let Some(palette) = &plan.palette {
+            let line_size = self.palette_buf.len();
+
+            if line_size > 0 {
+                palette.process(&mut self.palette_buf, line_size);
+
+                // Missing `tRNS` entries are opaque
+                if let Some(trns) = &mut self.trns_buf {
+                    trns.resize(line_size / 3, 0xFF);
+                    Self::permute_trns(trns, palette);
+                }
+            }
+        }
+
+        if plan.mix < 1.0 || plan.blend != MoshBlend::Normal {
+            self.blend(plan.mix, plan.blend);
+        }
+
+        Ok(())
     }
-}
 
-impl Default for MoshData {
-    fn default() -> Self {
-        Self {
-            buf: vec![0_u8],
-            image: vec![0_u8],
-            width: 1,
-            height: 1,
-            color_type: ColorType::Rgba,
-            bit_depth: BitDepth::Eight,
-            line_size: 1,
+    // Moves transparency along with palette entries, channel effects keep entries in place
+    fn permute_trns(trns: &mut [u8], plan: &MoshChunkPlan) {
+        for line in &plan.lines {
+            match *line {
+                MoshLine::Shift(amount) if !trns.is_empty() => {
+                    trns.rotate_left(amount / 3 % trns.len());
+                }
+                MoshLine::Reverse => trns.reverse(),
+                _ => {}
+            }
         }
     }
-}
 
-impl Default for MoshOptions {
-    fn default() -> Self {
+    fn filter_glitch(&mut self, plan: &MoshFilterPlan) {
+        let bpp = cmp::max(1, self.channel_count() * self.bit_depth as usize / 8);
+        let mut data = filter::filter(&self.buf, self.line_size, bpp, &plan.encode);
+
+        for op in &plan.ops {
+            op.glitch(&mut data);
+        }
+
+        filter::unfilter(&mut data, self.line_size, bpp, &plan.decode);
+        self.buf = data;
+        self.wrap_indices();
+    }
+
+    // Decodes as many rows as possible, the rest comes from the original image
+    fn deflate_glitch(&mut self, plan: &MoshDeflatePlan) -> Result<(), MoshError> {
+        let mut png = Vec::new();
+        let mut encoder = Encoder::new(&mut png, self.width, self.height);
+
+        encoder.set_color(self.color_type);
+        encoder.set_depth(self.bit_depth);
+
+        if self.color_type == ColorType::Indexed {
+            encoder.set_palette(self.palette.as_slice());
+        }
+
+        encoder.write_header()?.write_image_data(&self.buf)?;
+        deflate::corrupt(&mut png, &plan.ops);
+
+        let mut decoder = Decoder::new(png.as_slice());
+        let mut buf = self.image.clone();
+
+        decoder.ignore_checksums(true);
+
+        if let Ok(mut reader) = decoder.read_info() {
+            for line in buf.chunks_exact_mut(self.line_size) {
+                match reader.next_row() {
+                    Ok(Some(row)) if row.data().len() == line.len() => {
+                        line.copy_from_slice(row.data());
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        self.buf = buf;
+        self.wrap_indices();
+
+        Ok(())
+    }
+
+    // Blends the result over the original image
+    fn blend(&mut self, mix: f64, blend: MoshBlend) {
+        let channel_count = self.channel_count();
+        let alpha = self.effect_channels(true) < channel_count;
+        let mode = |sample: usize| {
+            if alpha && sample % channel_count == channel_count - 1 {
+                MoshBlend::Normal
+            } else {
+                blend
+            }
+        };
+
+        match (self.color_type, self.bit_depth) {
+            (ColorType::Indexed, _) => {
+                // Indices can not be blended, so pixels pick one with ordered dithering
+                let width = self.width as usize;
+                let original = self.unpack_samples(&self.image);
+                let mut indices = self.unpack_samples(&self.buf);
+
+                for (index, (top, base)) in indices.iter_mut().zip(original).enumerate() {
+                    let threshold = BAYER_MATRIX[index / width % 4][index % width % 4];
+
+                    if (f64::from(threshold) + 0.5) / 16.0 >= mix {
+                        *top = base;
+                    }
+                }
+
+                self.buf = self.pack_samples(&indices);
+
+                for (top, base) in self.palette_buf.iter_mut().zip(&self.palette) {
+                    *top = blend.apply(u32::from(*base), u32::from(*top), 0xFF, mix) as u8;
+                }
+
+                if let (Some(trns_buf), Some(trns)) = (&mut self.trns_buf, &self.trns) {
+                    for (top, base) in trns_buf.iter_mut().zip(trns) {
+                        *top = MoshBlend::Normal.apply(u32::from(*base), u32::from(*top), 0xFF, mix)
+                            as u8;
+                    }
+                }
+            }
+            (_, BitDepth::Sixteen) => {
+                for (sample, (top, base)) in self
+                    .buf
+                    .chunks_exact_mut(2)
+                    .zip(self.image.chunks_exact(2))
+                    .enumerate()
+                {
+                    let base = u16::from_be_bytes([base[0], base[1]]);
+                    let value = u16::from_be_bytes([top[0], top[1]]);
+                    let value = mode(sample).apply(u32::from(base), u32::from(value), 0xFFFF, mix);
+
+                    top.copy_from_slice(&(value as u16).to_be_bytes());
+                }
+            }
+            (_, BitDepth::Eight) => {
+                for (sample, (top, base)) in self.buf.iter_mut().zip(&self.image).enumerate() {
+                    *top = mode(sample).apply(u32::from(*base), u32::from(*top), 0xFF, mix) as u8;
+                }
+            }
+            _ => {
+                let max = (1_u32 << self.bit_depth as u32) - 1;
+                let original = self.unpack_samples(&self.image);
+                let mut samples = self.unpack_samples(&self.buf);
+
+                for (top, base) in samples.iter_mut().zip(original) {
+                    *top = blend.apply(u32::from(base), u32::from(*top), max, mix) as u8;
+                }
+
+                self.buf = self.pack_samples(&samples);
+            }
+        }
+    }
+
+    // Vertical chunks are processed on a transposed buffer
+    fn process_chunks<T: Copy>(
+        buf: &mut Vec<T>,
+        (width, height, channel_count): (usize, usize, usize),
+        alpha: bool,
+        chunks: &[impl Process],
+    ) where
+        dyn MoshEffect: Mosh<T>,
+    {
+        // Color channels are processed apart from alpha
+        if alpha {
+            let color_count = channel_count - 1;
+            let mut colors: Vec<T> = buf
+                .chunks_exact(channel_count)
+                .flat_map(|pixel| &pixel[..color_count])
+                .copied()
+                .collect();
+
+            Self::process_chunks(&mut colors, (width, height, color_count), false, chunks);
+
+            for (pixel, color) in buf
+                .chunks_exact_mut(channel_count)
+                .zip(colors.chunks_exact(color_count))
+            {
+                pixel[..color_count].copy_from_slice(color);
+            }
+
+            return;
+        }
+
+        let mut vertical = false;
+
+        for chunk in chunks {
+            if chunk.vertical() != vertical {
+                *buf = if vertical {
+                    Self::transpose(buf, height, width, channel_count)
+                } else {
+                    Self::transpose(buf, width, height, channel_count)
+                };
+
+                vertical = chunk.vertical();
+            }
+
+            let line_size = if vertical { height } else { width } * channel_count;
+            chunk.process(buf, line_size);
+        }
+
+        if vertical {
+            *buf = Self::transpose(buf, height, width, channel_count);
+        }
+    }
+
+    fn transpose<T: Copy>(buf: &[T], width: usize, height: usize, channel_count: usize) -> Vec<T> {
+        (0..width)
+            .flat_map(|x| (0..height).map(move |y| (y * width + x) * channel_count))
+            .flat_map(|position| &buf[position..position + channel_count])
+            .copied()
+            .collect()
+    }
+
+    fn restore_alpha(&mut self) {
+        let sample_size = if self.bit_depth == BitDepth::Sixteen {
+            2
+        } else {
+            1
+        };
+
+        let pixel_size = self.channel_count() * sample_size;
+        let alpha = pixel_size - sample_size..pixel_size;
+
+        for (pixel, original) in self
+            .buf
+            .chunks_exact_mut(pixel_size)
+            .zip(self.image.chunks_exact(pixel_size))
+        {
+            pixel[alpha.clone()].copy_from_slice(&original[alpha.clone()]);
+        }
+    }
+
+    // Channels available to effects
+    fn effect_channels(&self, preserve_alpha: bool) -> usize {
+        match self.color_type {
+            ColorType::GrayscaleAlpha | ColorType::Rgba if preserve_alpha => {
+                self.channel_count() - 1
+            }
+            _ => self.channel_count(),
+        }
+    }
+
+    fn channel_count(&self) -> usize {
+        match self.color_type {
+            ColorType::Grayscale | ColorType::Indexed => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    fn pixel_type(&self) -> fr::PixelType {
+        match (self.color_type, self.bit_depth) {
+            (ColorType::Grayscale, BitDepth::Sixteen) => fr::PixelType::U16,
+            (ColorType::GrayscaleAlpha, BitDepth::Sixteen) => fr::PixelType::U16x2,
+            (ColorType::Rgb, BitDepth::Sixteen) => fr::PixelType::U16x3,
+            (ColorType::Rgba, BitDepth::Sixteen) => fr::PixelType::U16x4,
+            (ColorType::Grayscale | ColorType::Indexed, _) => fr::PixelType::U8,
+            (ColorType::GrayscaleAlpha, _) => fr::PixelType::U8x2,
+            (ColorType::Rgb, _) => fr::PixelType::U8x3,
+            (ColorType::Rgba, _) => fr::PixelType::U8x4,
+        }
+    }
+
+    // PNG stores 16-bit samples in big-endian order
+    fn read_samples(buf: &[u8]) -> Vec<u16> {
+        buf.chunks_exact(2)
+            .map(|s| u16::from_be_bytes([s[0], s[1]]))
+            .collect()
+    }
+
+    // Unpacks 1, 2 and 4-bit samples from a (single channel) buffer, 8-bit ones are copied
+    fn unpack_samples(&self, buf: &[u8]) -> Vec<u8> {
+        if self.bit_depth == BitDepth::Eight {
+            return buf.to_vec();
+        }
+
+        let bits = self.bit_depth as usize;
+        let mask = (1_u8 << bits) - 1;
+        let width = self.width as usize;
+
+        buf.chunks_exact(self.line_size)
+            .flat_map(|row| {
+                (0..width).map(move |x| {
+                    let position = x * bits;
+                    let shift = 8 - bits - position % 8;
+
+                    (row[position / 8] >> shift) & mask
+                })
+            })
+            .collect()
+    }
+
+    fn pack_samples(&self, samples: &[u8]) -> Vec<u8> {
+        if self.bit_depth == BitDepth::Eight {
+            return samples.to_vec();
+        }
+
+        let bits = self.bit_depth as usize;
+        let mask = (1_u8 << bits) - 1;
+        let mut buf = vec![0_u8; self.line_size * self.height as usize];
+
+        for (row, samples) in buf
+            .chunks_exact_mut(self.line_size)
+            .zip(samples.chunks_exact(self.width as usize))
+        {
+            for (x, sample) in samples.iter().enumerate() {
+                let position = x * bits;
+                let shift = 8 - bits - position % 8;
+
+                row[position / 8] |= (sample & mask) << shift;
+            }
+        }
+
+        buf
+    }
+
+    // Indices past the end of the palette wrap around it
+    fn wrap_indices(&mut self) {
+        let palette_size = self.palette.len() / 3;
+
+        if self.color_type != ColorType::Indexed
+            || palette_size == 0
+            || palette_size >= 1 << self.bit_depth as usize
+        {
+            return;
+        }
+
+        let mut indices = self.unpack_samples(&self.buf);
+
+        for index in &mut indices {
+            *index = (usize::from(*index) % palette_size) as u8;
+        }
+
+        self.buf = self.pack_samples(&indices);
+    }
+
+    fn pixelation(&mut self, pixelation: u8, pixel_type: fr::PixelType) {
+        if pixelation > 1 {
+            let width = NonZeroU32::new(self.width).unwrap();
+            let height = NonZeroU32::new(self.height).unwrap();
+            let src_image =
+                fr::Image::from_vec_u8(width, height, self.buf.clone(), pixel_type).unwrap();
+
+            let dest_width = NonZeroU32::new(self.width / u32::from(pixelation)).unwrap();
+            let dest_height = NonZeroU32::new(self.height / u32::from(pixelation)).unwrap();
+            let orig_width = NonZeroU32::new(self.width).unwrap();
+            let orig_height = NonZeroU32::new(self.height).unwrap();
+
+            let mut dest_image = fr::Image::new(dest_width, dest_height, src_image.pixel_type());
+            let mut orig_image = fr::Image::new(orig_width, orig_height, src_image.pixel_type());
+            let mut dest_view = dest_image.view_mut();
+            let mut orig_view = orig_image.view_mut();
+            let mut resizer = fr::Resizer::new(fr::ResizeAlg::Nearest);
+
+            resizer.resize(&src_image.view(), &mut dest_view).unwrap();
+            resizer.resize(&dest_image.view(), &mut orig_view).unwrap();
+
+            self.buf = orig_image.into_vec();
+        }
+    }
+
+    fn plan_orientation(&self, rng: &mut impl Rng, options: &MoshOptions) -> bool {
+        match options.orientation {
+            MoshOrientation::Horizontal => false,
+            MoshOrientation::Vertical => true,
+            MoshOrientation::Random => rng.gen_bool(0.5),
+        }
+    }
+
+    // Line count and size (in samples) along the orientation
+    fn dimensions(&self, vertical: bool, channel_count: usize) -> (usize, usize) {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let (line_count, line_length) = if vertical {
+            (width, height)
+        } else {
+            (height, width)
+        };
+
+        (line_count, line_length * channel_count)
+    }
+
+    // Corruptions stay within a few lines, like scanline damage
+    fn plan_filter(
+        data_size: usize,
+        line_size: usize,
+        rng: &mut impl Rng,
+        options: &MoshOptions,
+    ) -> MoshFilterPlan {
+        const FILTERS: [MoshFilterType; 5] = [
+            MoshFilterType::None,
+            MoshFilterType::Sub,
+            MoshFilterType::Up,
+            MoshFilterType::Average,
+            MoshFilterType::Paeth,
+        ];
+
+        let line_count = data_size / line_size;
+        let encode: Vec<_> = (0..line_count)
+            .map(|_| {
+                options
+                    .filter
+                    .unwrap_or_else(|| FILTERS[rng.gen_range(0..FILTERS.len())])
+            })
+            .collect();
+
+        let decode = encode
+            .iter()
+            .map(|filter| {
+                if rng.gen_bool(options.filter_mismatch) {
+                    FILTERS[rng.gen_range(0..FILTERS.len())]
+                } else {
+                    *filter
+                }
+            })
+            .collect();
+
+        let max_length = cmp::min(data_size, line_size * 4);
+        let ops = (0..options.filter_glitch)
+            .map(|_| {
+                let length = rng.gen_range(1..=max_length);
+                let position = rng.gen_range(0..=data_size - length);
+
+                match rng.gen_range(0..3) {
+                    0 => MoshFilterOp::Graffiti(
+                        position,
+                        (0..cmp::min(length, 16)).map(|_| rng.gen()).collect(),
+                    ),
+                    1 => MoshFilterOp::Replace(position, length, rng.gen(), rng.gen()),
+                    _ => {
+                        let target = rng.gen_range(0..=data_size - length);
+                        MoshFilterOp::Transpose(position, target, length)
+                    }
+                }
+            })
+            .collect();
+
+        MoshFilterPlan {
+            encode,
+            decode,
+            ops,
+        }
+    }
+
+    fn plan_palette(
+        entry_count: usize,
+        rng: &mut impl Rng,
+        options: &MoshOptions,
+    ) -> Option<MoshChunkPlan> {
+        if entry_count == 0 {
+            return None;
+        }
+
+        let entry_distrib = Uniform::from(0..entry_count);
+        let channel_distrib = Uniform::from(0..3);
+        let mut lines = Vec::new();
+        let mut chunk = Vec::new();
+
+        if rng.gen_bool(options.line_shift) {
+            let amount = entry_distrib.sample(rng) * 3;
+            lines.push(MoshLine::Shift(amount));
+        }
+
+        if rng.gen_bool(options.reverse) {
+            lines.push(MoshLine::Reverse);
+        }
+
+        if rng.gen_bool(options.channel_shift) {
+            let amount = options.shift_unit.align(entry_distrib.sample(rng), 3);
+            let channel = channel_distrib.sample(rng);
+            lines.push(MoshLine::ChannelShift(amount, channel, 3));
+        }
+
+        if rng.gen_bool(options.channel_swap) {
+            let channel_1 = channel_distrib.sample(rng);
+            let channel_2 = channel_distrib.sample(rng);
+            chunk.push(MoshChunk::ChannelSwap(channel_1, channel_2, 3));
+        }
+
+        Some(MoshChunkPlan {
+            first_line: 0,
+            last_line: 1,
+            columns: None,
+            vertical: false,
+            color: None,
+            lines,
+            chunk,
+            sort: None,
+            split: None,
+            wave: None,
+            blocks: None,
+            smear: None,
+            crush: None,
+            region: None,
+            skew: None,
+            bytes: Vec::new(),
+        })
+    }
+
+    fn plan_chunk(
+        line_count: usize,
+        line_size: usize,
+        channel_count: usize,
+        depth: u8,
+        rng: &mut Streams,
+        options: &MoshOptions,
+    ) -> MoshChunkPlan {
+        let (first_line, last_line, columns) = Self::plan_geometry(
+            line_count,
+            line_size,
+            channel_count,
+            rng.get(Stream::Chunk),
+            options,
+        );
+
+        let block_size = columns.map_or(line_size, |(first, last)| last - first);
+        let line_shift_distrib = Uniform::from(0..block_size);
+        let channel_count_distrib = Uniform::from(0..channel_count);
+
+        let reverse = rng.get(Stream::Reverse).gen_bool(options.reverse);
+        let flip = rng.get(Stream::Flip).gen_bool(options.flip);
+
+        let line_shift = rng
+            .get(Stream::LineShift)
+            .gen_bool(options.line_shift)
+            .then(|| {
+                let line_shift_amount = line_shift_distrib.sample(rng.get(Stream::LineShift));
+                MoshLine::Shift(options.shift_unit.align(line_shift_amount, channel_count))
+            });
+
+        let channel_shift = rng
+            .get(Stream::ChannelShift)
+            .gen_bool(options.channel_shift)
+            .then(|| {
+                let rng = rng.get(Stream::ChannelShift);
+                let amount = line_shift_distrib.sample(rng) / channel_count;
+                let channel = channel_count_distrib.sample(rng);
+                MoshLine::ChannelShift(
+                    options.shift_unit.align(amount, channel_count),
+                    channel,
+                    channel_count,
+                )
+            });
+
+        let channel_swap = rng
+            .get(Stream::ChannelSwap)
+            .gen_bool(options.channel_swap)
+            .then(|| {
+                let rng = rng.get(Stream::ChannelSwap);
+                let channel_1 = channel_count_distrib.sample(rng);
+                let channel_2 = channel_count_distrib.sample(rng);
+                MoshChunk::ChannelSwap(channel_1, channel_2, channel_count)
+            });
+
+        let sort =
+            (options.sort > 0.0 && rng.get(Stream::Sort).gen_bool(options.sort)).then(|| {
+                let runs = match options.sort_runs {
+                    MoshSortMode::Threshold => {
+                        MoshSortRuns::Threshold(options.sort_min, options.sort_max)
+                    }
+                    MoshSortMode::Interval => {
+                        let rng = rng.get(Stream::Sort);
+                        let length_distrib =
+                            Uniform::from(1..=cmp::max(usize::from(options.sort_interval), 1));
+                        MoshSortRuns::Interval(
+                            (0..16).map(|_| length_distrib.sample(rng)).collect(),
+                        )
+                    }
+                };
+
+                MoshSort {
+                    channel_count,
+                    depth,
+                    key: match options.sort_key {
+                        MoshSortKey::Channel(channel) => {
+                            MoshSortKey::Channel(cmp::min(channel, channel_count - 1))
+                        }
+                        key => key,
+                    },
+                    runs,
+                }
+            });
+
+        let split = (options.rgb_split > 0.0
+            && rng.get(Stream::RgbSplit).gen_bool(options.rgb_split))
+        .then(|| {
+            let color_count = match channel_count {
+                2 | 4 => channel_count - 1,
+                _ => channel_count,
+            };
+
+            let offsets = if options.rgb_split_offsets.is_empty() {
+                let rng = rng.get(Stream::RgbSplit);
+                let range = i32::from(options.rgb_split_range);
+                let offset_distrib = Uniform::from(-range..=range);
+
+                (0..color_count)
+                    .map(|_| (offset_distrib.sample(rng), offset_distrib.sample(rng)))
+                    .collect()
+            } else {
+                options
+                    .rgb_split_offsets
+                    .iter()
+                    .copied()
+                    .take(color_count)
+                    .collect()
+            };
+
+            MoshSplit {
+                line_size: block_size,
+                channel_count,
+                offsets,
+                edge: options.rgb_split_edge,
+            }
+        });
+
+        let wave =
+            (options.wave > 0.0 && rng.get(Stream::Wave).gen_bool(options.wave)).then(|| {
+                let period = cmp::max(usize::from(options.wave_period), 1);
+                let phase = usize::from(options.wave_phase);
+                let noise = match options.wave_shape {
+                    MoshWaveShape::Noise => {
+                        let rng = rng.get(Stream::Wave);
+                        let amplitude = i32::from(options.wave_amplitude);
+                        let offset_distrib = Uniform::from(-amplitude..=amplitude);
+                        let point_count = (last_line - first_line + phase) / period + 2;
+
+                        (0..point_count)
+                            .map(|_| offset_distrib.sample(rng))
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                };
+
+                MoshWave {
+                    line_size: block_size,
+                    channel_count,
+                    shape: options.wave_shape,
+                    amplitude: usize::from(options.wave_amplitude),
+                    period,
+                    phase,
+                    noise,
+                }
+            });
+
+        let blocks = (options.blocks > 0.0 && rng.get(Stream::Blocks).gen_bool(options.blocks))
+            .then(|| {
+                Self::plan_blocks(
+                    block_size,
+                    last_line - first_line,
+                    channel_count,
+                    options.block_size,
+                    options.block_displacement,
+                    rng.get(Stream::Blocks),
+                )
+            });
+
+        let smear =
+            (options.smear > 0.0 && rng.get(Stream::Smear).gen_bool(options.smear)).then(|| {
+                Self::plan_smear(
+                    block_size,
+                    last_line - first_line,
+                    channel_count,
+                    options,
+                    rng.get(Stream::Smear),
+                )
+            });
+
+        let region = Self::plan_region(
+            line_count,
+            first_line,
+            last_line,
+            options,
+            rng.get(Stream::Region),
+        );
+
+        let skew = (options.skew > 0.0 && rng.get(Stream::Skew).gen_bool(options.skew))
+            .then(|| Self::plan_skew(block_size, options, rng.get(Stream::Skew)))
+            .flatten();
+
+        let chunk_size = (last_line - first_line) * block_size;
+        let position_distrib = Uniform::from(0..cmp::max(chunk_size, 1));
+        let (bits, mask) = Self::sample_bits(depth);
+
+        // Disabled byte mutations draw nothing, leaving the other effects intact
+        let bit_flip = (options.bit_flip > 0.0
+            && rng.get(Stream::BitFlip).gen_bool(options.bit_flip))
+        .then(|| {
+            let rng = rng.get(Stream::BitFlip);
+
+            (0..options.bit_flip_intensity)
+                .map(|_| MoshByte::BitFlip(position_distrib.sample(rng), rng.gen_range(0..bits)))
+                .collect::<Vec<_>>()
+        });
+
+        let byte_replace = (options.byte_replace > 0.0
+            && rng.get(Stream::ByteReplace).gen_bool(options.byte_replace))
+        .then(|| {
+            let rng = rng.get(Stream::ByteReplace);
+            let position = position_distrib.sample(rng);
+            let pattern = (0..rng.gen_range(1..=4))
+                .map(|_| rng.gen::<u8>() & mask)
+                .collect();
+            let length = usize::from(options.byte_replace_intensity);
+            MoshByte::Replace(position, length, pattern)
+        });
+
+        let byte_insert = (options.byte_insert > 0.0
+            && rng.get(Stream::ByteInsert).gen_bool(options.byte_insert))
+        .then(|| {
+            let rng = rng.get(Stream::ByteInsert);
+            let position = position_distrib.sample(rng);
+            let bytes = (0..options.byte_insert_intensity)
+                .map(|_| rng.gen::<u8>() & mask)
+                .collect();
+            MoshByte::Insert(position, bytes)
+        });
+
+        let byte_delete = (options.byte_delete > 0.0
+            && rng.get(Stream::ByteDelete).gen_bool(options.byte_delete))
+        .then(|| {
+            let position = position_distrib.sample(rng.get(Stream::ByteDelete));
+            MoshByte::Delete(position, usize::from(options.byte_delete_intensity))
+        });
+
+        MoshChunkPlan {
+            first_line,
+            last_line,
+            columns,
+            vertical: false,
+            color: (channel_count >= 3
+                && options.color_space != MoshColorSpace::Rgb
+                && (channel_shift.is_some() || channel_swap.is_some()))
+            .then_some(MoshColor {
+                channel_count,
+                space: options.color_space,
+                decode: false,
+            }),
+            lines: [
+                channel_shift,
+                line_shift,
+                reverse.then_some(MoshLine::Reverse),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            chunk: [channel_swap, flip.then_some(MoshChunk::Flip)]
+                .into_iter()
+                .flatten()
+                .collect(),
+            sort,
+            split,
+            wave,
+            blocks,
+            smear,
+            crush: None,
+            region,
+            skew,
+            bytes: bit_flip
+                .into_iter()
+                .flatten()
+                .chain(
+                    [byte_replace, byte_insert, byte_delete]
+                        .into_iter()
+                        .flatten(),
+                )
+                .collect(),
+        }
+    }
+
+    // Macroblock mutations of a chunk
+    fn plan_blocks<R: Rng + ?Sized>(
+        line_size: usize,
+        line_count: usize,
+        channel_count: usize,
+        size: u16,
+        displacement: f64,
+        rng: &mut R,
+    ) -> MoshBlocks {
+        let offset = i32::from(size);
+        let size = usize::from(size);
+        let columns = (line_size / channel_count).checked_div(size).unwrap_or(0);
+        let rows = line_count.checked_div(size).unwrap_or(0);
+        let mut ops = Vec::new();
+
+        if columns > 0 && rows > 0 {
+            let column_distrib = Uniform::from(0..columns);
+            let row_distrib = Uniform::from(0..rows);
+            let offset_distrib = Uniform::from(-offset..=offset);
+
+            for row in 0..rows {
+                for column in 0..columns {
+                    if !rng.gen_bool(displacement) {
+                        continue;
+                    }
+
+                    let block = (column, row);
+                    let other = (column_distrib.sample(rng), row_distrib.sample(rng));
+
+                    ops.push(match rng.gen_range(0..3) {
+                        0 => MoshBlockOp::Displace(
+                            block,
+                            offset_distrib.sample(rng),
+                            offset_distrib.sample(rng),
+                        ),
+                        1 => MoshBlockOp::Duplicate(other, block),
+                        _ => MoshBlockOp::Swap(block, other),
+                    });
+                }
+            }
+        }
+
+        MoshBlocks {
+            line_size,
+            channel_count,
+            size,
+            ops,
+        }
+    }
+
+    // Streak of a chunk
+    fn plan_smear<R: Rng + ?Sized>(
+        line_size: usize,
+        line_count: usize,
+        channel_count: usize,
+        options: &MoshOptions,
+        rng: &mut R,
+    ) -> MoshSmear {
+        let vertical = match options.smear_direction {
+            MoshOrientation::Horizontal => false,
+            MoshOrientation::Vertical => true,
+            MoshOrientation::Random => rng.gen_bool(0.5),
+        };
+
+        let width = line_size / cmp::max(channel_count, 1);
+        let (count, span_count) = if vertical {
+            (line_count, width)
+        } else {
+            (width, line_count)
+        };
+
+        let source = rng.gen_range(0..cmp::max(count, 1));
+        let length = rng.gen_range(1..=cmp::max(usize::from(options.smear_length), 1));
+        let span = options.smear_partial.then(|| {
+            let first = rng.gen_range(0..cmp::max(span_count, 1));
+            let last = rng.gen_range(first + 1..=cmp::max(span_count, first + 1));
+            (first, last)
+        });
+
+        MoshSmear {
+            line_size,
+            channel_count,
+            vertical,
+            source,
+            length,
+            span,
+        }
+    }
+
+    // Stride error of a chunk, none without a range
+    fn plan_skew<R: Rng + ?Sized>(
+        line_size: usize,
+        options: &MoshOptions,
+        rng: &mut R,
+    ) -> Option<MoshSkew> {
+        let range = cmp::min(usize::from(options.skew_range), line_size) as isize;
+
+        (range > 0).then(|| {
+            let skew = rng.gen_range(1..=range);
+            let left = match options.skew_direction {
+                MoshSkewDirection::Left => true,
+                MoshSkewDirection::Right => false,
+                MoshSkewDirection::Random => rng.gen_bool(0.5),
+            };
+
+            MoshSkew {
+                line_size,
+                skew: if left { skew } else { -skew },
+            }
+        })
+    }
+
+    // Bits to flip and mask of written values, sub-byte samples keep within their depth
+    fn sample_bits(depth: u8) -> (u8, u8) {
+        if depth < 8 {
+            (depth, (1 << depth) - 1)
+        } else {
+            (16, u8::MAX)
+        }
+    }
+
+    // Bitcrush of a chunk, indices of indexed images are left alone
+    fn plan_crush<R: Rng + ?Sized>(
+        &self,
+        line_size: usize,
+        channel_count: usize,
+        rng: &mut R,
+        options: &MoshOptions,
+    ) -> Option<MoshCrush> {
+        (self.color_type != ColorType::Indexed
+            && options.crush > 0.0
+            && rng.gen_bool(options.crush))
+        .then(|| MoshCrush {
+            line_size,
+            channel_count,
+            depth: self.bit_depth as u8,
+            bits: options.crush_bits.clone(),
+            dither: options.crush_dither,
+        })
+    }
+
+    // Relocation of a chunk, swaps only target regions clear of it
+    fn plan_region<R: Rng + ?Sized>(
+        line_count: usize,
+        first_line: usize,
+        last_line: usize,
+        options: &MoshOptions,
+        rng: &mut R,
+    ) -> Option<MoshRegion> {
+        let size = last_line - first_line;
+
+        if size == 0 || size > line_count {
+            return None;
+        }
+
+        let copy = options.chunk_copy > 0.0 && rng.gen_bool(options.chunk_copy);
+        let swap = options.chunk_swap > 0.0 && rng.gen_bool(options.chunk_swap);
+        let tile = options.chunk_tile > 0.0 && rng.gen_bool(options.chunk_tile);
+
+        if copy {
+            Some(MoshRegion::Copy(rng.gen_range(0..=line_count - size)))
+        } else if swap {
+            let before = (first_line + 1).saturating_sub(size);
+            let after = (line_count + 1).saturating_sub(last_line + size);
+
+            (before + after > 0).then(|| {
+                let target = rng.gen_range(0..before + after);
+
+                if target < before {
+                    MoshRegion::Swap(target)
+                } else {
+                    MoshRegion::Swap(last_line + target - before)
+                }
+            })
+        } else {
+            tile.then(|| {
+                let count = cmp::max(usize::from(options.chunk_tile_count), 1);
+                MoshRegion::Tile(rng.gen_range(1..=count))
+            })
+        }
+    }
+
+    // Lines and, for rectangular chunks, columns (in samples) of a chunk
+    fn plan_geometry(
+        line_count: usize,
+        line_size: usize,
+        channel_count: usize,
+        rng: &mut impl Rng,
+        options: &MoshOptions,
+    ) -> (usize, usize, Option<(usize, usize)>) {
+        let (first_line, last_line) =
+            if options.min_chunk_height.is_none() && options.max_chunk_height.is_none() {
+                let line_number_distrib = Uniform::from(0..line_count);
+                let first_line = line_number_distrib.sample(rng);
+                let chunk_size = line_number_distrib.sample(rng) / 2;
+                let last_line = if (first_line + chunk_size) > line_count {
+                    line_count
+                } else {
+                    first_line + chunk_size
+                };
+
+                (first_line, last_line)
+            } else {
+                let min_height = options
+                    .min_chunk_height
+                    .map_or(0, |size| size.resolve(line_count))
+                    .min(line_count);
+
+                let max_height = options
+                    .max_chunk_height
+                    .map_or(line_count / 2, |size| size.resolve(line_count))
+                    .clamp(min_height, line_count);
+
+                let chunk_size = Uniform::from(min_height..=max_height).sample(rng);
+                let first_line = Uniform::from(0..=line_count - chunk_size).sample(rng);
+
+                (first_line, first_line + chunk_size)
+            };
+
+        let columns = (options.min_chunk_width.is_some() || options.max_chunk_width.is_some())
+            .then(|| {
+                let width = line_size / channel_count;
+                let min_width = options
+                    .min_chunk_width
+                    .map_or(1, |size| size.resolve(width))
+                    .clamp(1, width);
+
+                let max_width = options
+                    .max_chunk_width
+                    .map_or(width, |size| size.resolve(width))
+                    .clamp(min_width, width);
+
+                let chunk_width = Uniform::from(min_width..=max_width).sample(rng);
+                let first_column = Uniform::from(0..=width - chunk_width).sample(rng);
+
+                (
+                    first_column * channel_count,
+                    (first_column + chunk_width) * channel_count,
+                )
+            });
+
+        (first_line, last_line, columns)
+    }
+
+    // Blocks are gathered before being written back, so overlapping ranges are safe
+    fn relocate<T: Copy>(
+        buf: &mut [T],
+        line_size: usize,
+        lines: (usize, usize),
+        columns: Option<(usize, usize)>,
+        region: &MoshRegion,
+    ) {
+        let line_count = buf.len() / line_size;
+        let last_line = cmp::min(lines.1, line_count);
+        let first_line = cmp::min(lines.0, last_line);
+        let (first_column, last_column) = columns.map_or((0, line_size), |(first, last)| {
+            let last = cmp::min(last, line_size);
+            (cmp::min(first, last), last)
+        });
+
+        let size = last_line - first_line;
+        let block_size = last_column - first_column;
+
+        if size == 0 || block_size == 0 {
+            return;
+        }
+
+        let gather = |buf: &[T], line: usize| -> Vec<T> {
+            buf[line * line_size..(line + size) * line_size]
+                .chunks_exact(line_size)
+                .flat_map(|line| &line[first_column..last_column])
+                .copied()
+                .collect()
+        };
+
+        // Lines past the end of the image are dropped
+        let scatter = |buf: &mut [T], line: usize, block: &[T]| {
+            for (line, values) in buf[line * line_size..]
+                .chunks_exact_mut(line_size)
+                .zip(block.chunks_exact(block_size))
+            {
+                line[first_column..last_column].copy_from_slice(values);
+            }
+        };
+
+        match *region {
+            MoshRegion::Copy(line) => {
+                let target = cmp::min(line, line_count - size);
+                let block = gather(buf, first_line);
+                scatter(buf, target, &block);
+            }
+            MoshRegion::Swap(line) => {
+                let target = cmp::min(line, line_count - size);
+
+                if target + size <= first_line || target >= last_line {
+                    let block = gather(buf, first_line);
+                    let other = gather(buf, target);
+                    scatter(buf, first_line, &other);
+                    scatter(buf, target, &block);
+                }
+            }
+            MoshRegion::Tile(count) => {
+                let block = gather(buf, first_line);
+
+                for repeat in 1..=count {
+                    let line = last_line + (repeat - 1) * size;
+
+                    if line >= line_count {
+                        break;
+                    }
+
+                    scatter(buf, line, &block);
+                }
+            }
+        }
+    }
+
+    fn chunkmosh<'a, T: Copy, E: Mosh<T> + ?Sized + 'a>(
+        buf: &mut [T],
+        line_size: usize,
+        lines: (usize, usize),
+        columns: Option<(usize, usize)>,
+        effects: impl IntoIterator<Item = (MoshLevel, &'a E)>,
+    ) {
+        let line_count = buf.len() / line_size;
+        let last_line = cmp::min(lines.1, line_count);
+        let first_line = cmp::min(lines.0, last_line);
+        let (first_column, last_column) = columns.map_or((0, line_size), |(first, last)| {
+            let last = cmp::min(last, line_size);
+            (cmp::min(first, last), last)
+        });
+
+        let block_size = last_column - first_column;
+        let chunk = &mut buf[first_line * line_size..last_line * line_size];
+
+        if block_size == 0 {
+            return;
+        }
+
+        for (level, effect) in effects {
+            match level {
+                MoshLevel::Line => {
+                    for line in chunk.chunks_exact_mut(line_size) {
+                        effect.glitch(&mut line[first_column..last_column]);
+                    }
+                }
+                MoshLevel::Chunk if block_size == line_size => effect.glitch(chunk),
+                MoshLevel::Chunk => {
+                    // Rectangular chunks are glitched as a contiguous block
+                    let mut block: Vec<T> = chunk
+                        .chunks_exact(line_size)
+                        .flat_map(|line| &line[first_column..last_column])
+                        .copied()
+                        .collect();
+
+                    effect.glitch(&mut block);
+
+                    for (line, values) in chunk
+                        .chunks_exact_mut(line_size)
+                        .zip(block.chunks_exact(block_size))
+                    {
+                        line[first_column..last_column].copy_from_slice(values);
+                    }
+                }
+            }
+        }
+    }
+}
+// Chunk processing, for samples of any supported bit depth
+trait Process {
+    fn vertical(&self) -> bool;
+
+    fn process<T: Copy>(&self, buf: &mut [T], line_size: usize)
+    where
+        dyn MoshEffect: Mosh<T>;
+}
+
+impl Process for MoshChunkPlan {
+    fn vertical(&self) -> bool {
+        self.vertical
+    }
+
+    fn process<T: Copy>(&self, buf: &mut [T], line_size: usize)
+    where
+        dyn MoshEffect: Mosh<T>,
+    {
+        let color = self.color.map(|color| {
+            (
+                MoshColor {
+                    decode: false,
+                    ..color
+                },
+                MoshColor {
+                    decode: true,
+                    ..color
+                },
+            )
+        });
+        let color = color.as_ref();
+        let lines = self.lines.iter().flat_map(|line| {
+            let color = color.filter(|_| matches!(line, MoshLine::ChannelShift(..)));
+            converted(MoshLevel::Line, line, color)
+        });
+        let chunk = self.chunk.iter().flat_map(|chunk| {
+            let color = color.filter(|_| matches!(chunk, MoshChunk::ChannelSwap(..)));
+            converted(MoshLevel::Chunk, chunk, color)
+        });
+        let sort = self
+            .sort
+            .iter()
+            .map(|sort| (MoshLevel::Line, sort as &dyn MoshEffect));
+        let split = self
+            .split
+            .iter()
+            .map(|split| (MoshLevel::Chunk, split as &dyn MoshEffect));
+        let wave = self
+            .wave
+            .iter()
+            .map(|wave| (MoshLevel::Chunk, wave as &dyn MoshEffect));
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|blocks| (MoshLevel::Chunk, blocks as &dyn MoshEffect));
+        let smear = self
+            .smear
+            .iter()
+            .map(|smear| (MoshLevel::Chunk, smear as &dyn MoshEffect));
+        let crush = self
+            .crush
+            .iter()
+            .map(|crush| (MoshLevel::Chunk, crush as &dyn MoshEffect));
+        let skew = self
+            .skew
+            .iter()
+            .map(|skew| (MoshLevel::Chunk, skew as &dyn MoshEffect));
+        let bytes = self
+            .bytes
+            .iter()
+            .map(|byte| (MoshLevel::Chunk, byte as &dyn MoshEffect));
+
+        let range = (self.first_line, self.last_line);
+
+        MoshData::chunkmosh(buf, line_size, range, self.columns, lines);
+        MoshData::chunkmosh(buf, line_size, range, self.columns, chunk);
+        MoshData::chunkmosh(buf, line_size, range, self.columns, sort);
+        MoshData::chunkmosh(buf, line_size, range, self.columns, split);
+        MoshData::chunkmosh(buf, line_size, range, self.columns, wave);
+        MoshData::chunkmosh(buf, line_size, range, self.columns, blocks);
+        MoshData::chunkmosh(buf, line_size, range, self.columns, smear);
+        MoshData::chunkmosh(buf, line_size, range, self.columns, crush);
+
+        if let Some(region) = &self.region {
+            MoshData::relocate(buf, line_size, range, self.columns, region);
+        }
+
+        MoshData::chunkmosh(buf, line_size, range, self.columns, skew);
+
+        MoshData::chunkmosh(buf, line_size, range, self.columns, bytes);
+    }
+}
+
+// Runs a channel effect between color space conversions
+fn converted<'a>(
+    level: MoshLevel,
+    effect: &'a (dyn MoshEffect + 'static),
+    color: Option<&'a (MoshColor, MoshColor)>,
+) -> impl Iterator<Item = (MoshLevel, &'a (dyn MoshEffect + 'static))> {
+    let encode = color.map(|(encode, _)| (level, encode as &dyn MoshEffect));
+    let decode = color.map(|(_, decode)| (level, decode as &dyn MoshEffect));
+
+    encode.into_iter().chain([(level, effect)]).chain(decode)
+}
+
+// Chunk with sampled pipeline effects
+struct PipelineChunk {
+    first_line: usize,
+    last_line: usize,
+    columns: Option<(usize, usize)>,
+    vertical: bool,
+    effects: Vec<(MoshLevel, Box<dyn MoshEffect>)>,
+}
+
+impl Process for PipelineChunk {
+    fn vertical(&self) -> bool {
+        self.vertical
+    }
+
+    fn process<T: Copy>(&self, buf: &mut [T], line_size: usize)
+    where
+        dyn MoshEffect: Mosh<T>,
+    {
+        let effects = self
+            .effects
+            .iter()
+            .map(|(level, effect)| (*level, effect.as_ref()));
+
+        let range = (self.first_line, self.last_line);
+
+        MoshData::chunkmosh(buf, line_size, range, self.columns, effects);
+    }
+}
+
+// Random streams, appended as effects are added so existing seeds stay stable
+#[derive(Clone, Copy)]
+enum Stream {
+    Rate,
+    Chunk,
+    Reverse,
+    Flip,
+    LineShift,
+    ChannelShift,
+    ChannelSwap,
+    Palette,
+    Filter,
+    Deflate,
+    BitFlip,
+    ByteReplace,
+    ByteInsert,
+    ByteDelete,
+    Sort,
+    RgbSplit,
+    Wave,
+    Blocks,
+    Smear,
+    Region,
+    Crush,
+    Skew,
+}
+
+const STREAM_COUNT: usize = Stream::Skew as usize + 1;
+
+// A single shared generator or one per stream, keyed by the same seed.
+// Pipeline stages follow the built-in streams.
+struct Streams(Vec<ChaCha8Rng>);
+
+impl Streams {
+    fn new(seed: u64, independent: bool, stage_count: usize) -> Self {
+        let rng = ChaCha8Rng::seed_from_u64(seed);
+
+        if independent {
+            Self(
+                (0..(STREAM_COUNT + stage_count) as u64)
+                    .map(|stream| {
+                        let mut rng = rng.clone();
+                        rng.set_stream(stream);
+                        rng
+                    })
+                    .collect(),
+            )
+        } else {
+            Self(vec![rng])
+        }
+    }
+
+    fn get(&mut self, stream: Stream) -> &mut ChaCha8Rng {
+        self.nth(stream as usize)
+    }
+
+    fn stage(&mut self, index: usize) -> &mut ChaCha8Rng {
+        self.nth(STREAM_COUNT + index)
+    }
+
+    fn nth(&mut self, index: usize) -> &mut ChaCha8Rng {
+        let index = cmp::min(index, self.0.len() - 1);
+
+        &mut self.0[index]
+    }
+}
+
+impl Default for MoshData {
+    fn default() -> Self {
+        Self {
+            buf: vec![0_u8],
+            image: vec![0_u8],
+            width: 1,
+            height: 1,
+            color_type: ColorType::Rgba,
+            bit_depth: BitDepth::Eight,
+            line_size: 1,
+            palette_buf: Vec::new(),
+            palette: Vec::new(),
+            trns_buf: None,
+            trns: None,
+        }
+    }
+}
+
+impl Default for MoshOptions {
+    fn default() -> Self {
         Self {
             min_rate: 1,
             max_rate: 7,
+            min_chunk_height: None,
+            max_chunk_height: None,
+            min_chunk_width: None,
+            max_chunk_width: None,
             pixelation: 10,
             line_shift: 0.3,
             reverse: 0.3,
             flip: 0.3,
             channel_swap: 0.3,
             channel_shift: 0.3,
+            shift_unit: MoshShiftUnit::default(),
+            color_space: MoshColorSpace::default(),
+            bit_flip: 0.0,
+            bit_flip_intensity: 8,
+            byte_replace: 0.0,
+            byte_replace_intensity: 16,
+            byte_insert: 0.0,
+            byte_insert_intensity: 16,
+            byte_delete: 0.0,
+            byte_delete_intensity: 16,
+            sort: 0.0,
+            sort_key: MoshSortKey::default(),
+            sort_runs: MoshSortMode::default(),
+            sort_min: 64,
+            sort_max: 224,
+            sort_interval: 64,
+            rgb_split: 0.0,
+            rgb_split_range: 8,
+            rgb_split_offsets: Vec::new(),
+            rgb_split_edge: MoshEdge::default(),
+            wave: 0.0,
+            wave_shape: MoshWaveShape::default(),
+            wave_amplitude: 8,
+            wave_period: 32,
+            wave_phase: 0,
+            blocks: 0.0,
+            block_size: 16,
+            block_displacement: 0.1,
+            smear: 0.0,
+            smear_length: 64,
+            smear_direction: MoshOrientation::Vertical,
+            smear_partial: false,
+            chunk_copy: 0.0,
+            chunk_swap: 0.0,
+            chunk_tile: 0.0,
+            chunk_tile_count: 4,
+            crush: 0.0,
+            crush_bits: vec![4],
+            crush_dither: MoshDither::None,
+            crush_global: false,
+            skew: 0.0,
+            skew_range: 8,
+            skew_direction: MoshSkewDirection::Random,
+            filter_glitch: 0,
+            filter: None,
+            filter_mismatch: 0.3,
+            deflate_glitch: 0,
             seed: Self::generate_seed(),
+            preserve_alpha: false,
+            mix: 1.0,
+            blend: MoshBlend::default(),
+            orientation: MoshOrientation::default(),
+            independent_streams: false,
+            indexed: IndexedMode::default(),
+        }
+    }
+}
+
+impl Default for MoshPlan {
+    fn default() -> Self {
+        Self {
+            chunks: Vec::new(),
+            palette: None,
+            filter: None,
+            deflate: None,
+            pixelation: 1,
+            preserve_alpha: false,
+            mix: 1.0,
+            blend: MoshBlend::default(),
         }
     }
 }
 
+// Ordered dithering thresholds, in sixteenths
+const BAYER_MATRIX: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
 const TEST_SEED: u64 = 901_042_006;
 
 #[cfg(test)]
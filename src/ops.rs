@@ -51,3 +51,38 @@ pub fn write_file(
 
     Ok(())
 }
+
+/// Writes a new [`Indexed`] file from the provided buffer and palette
+///
+/// # Errors
+///
+/// It may fail if parameters are invalid or due I/O error.
+///
+/// [`Indexed`]: ColorType::Indexed
+pub fn write_indexed_file(
+    dest: &str,
+    buf: &[u8],
+    width: u32,
+    height: u32,
+    bit_depth: BitDepth,
+    palette: &[u8],
+    trns: Option<&[u8]>,
+) -> Result<(), MoshError> {
+    let path = Path::new(&dest);
+    let output = File::create(path)?;
+    let buf_writer = &mut BufWriter::new(output);
+    let mut encoder = Encoder::new(buf_writer, width, height);
+
+    encoder.set_color(ColorType::Indexed);
+    encoder.set_depth(bit_depth);
+    encoder.set_palette(palette);
+
+    if let Some(trns) = trns {
+        encoder.set_trns(trns);
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(buf)?;
+
+    Ok(())
+}
@@ -0,0 +1,421 @@
+//! Effect pipelines
+
+use rand::{
+    distributions::{Distribution, Uniform},
+    Rng, RngCore,
+};
+
+use std::{cmp, sync::Arc};
+
+use crate::{
+    err::MoshError,
+    fx::{
+        Mosh, MoshByte, MoshChunk, MoshLine, MoshSkew, MoshSort, MoshSortKey, MoshSortRuns,
+        MoshSplit, MoshWave,
+    },
+    MoshData, MoshOptions, MoshSortMode, MoshWaveShape,
+};
+
+/// Effect applicable to both 8-bit and 16-bit samples
+///
+/// Implemented for every type implementing [`Mosh`] for `u8` and `u16`.
+pub trait MoshEffect: Mosh<u8> + Mosh<u16> + Send + Sync {}
+
+impl<E: Mosh<u8> + Mosh<u16> + Send + Sync> MoshEffect for E {}
+
+/// Effect level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoshLevel {
+    /// Applied to each line of a chunk.
+    Line,
+    /// Applied to the whole chunk.
+    Chunk,
+}
+
+/// Chunk parameters passed to samplers
+///
+/// Sizes are in samples.
+#[derive(Clone, Copy, Debug)]
+pub struct MoshContext {
+    /// Line size.
+    pub line_size: usize,
+    /// Lines in the chunk.
+    pub line_count: usize,
+    /// Channels per pixel.
+    pub channel_count: usize,
+    /// Sample bit depth.
+    pub depth: u8,
+}
+
+/// Parameter sampler, returns a configured effect
+pub type MoshSampler =
+    Arc<dyn Fn(&mut dyn RngCore, &MoshContext) -> Box<dyn MoshEffect> + Send + Sync>;
+
+/// Registered effect
+#[derive(Clone)]
+pub struct MoshStage {
+    /// Effect level.
+    pub level: MoshLevel,
+    /// Chance of the effect, per chunk.
+    pub chance: f64,
+    /// Parameter sampler.
+    pub sampler: MoshSampler,
+}
+
+/**
+Effect pipeline.
+
+Holds effects applied to every chunk, in order. Run it with [`MoshCore::mosh_with`].
+
+# Example
+```rust
+use libmosh::{
+    err::MoshError,
+    fx::Mosh,
+    ops::read_file,
+    pipeline::MoshPipeline,
+    MoshCore,
+};
+
+struct Rotate(usize);
+
+impl<T> Mosh<T> for Rotate {
+    fn glitch(&self, value: &mut [T]) {
+        let amount = self.0 % value.len().max(1);
+        value.rotate_right(amount);
+    }
+}
+
+let input = read_file("src/util/test-rgb.png")?;
+let mut image = MoshCore::new();
+let pipeline = MoshPipeline::from_options(&image.options).chunk(0.5, |rng, context| {
+    Box::new(Rotate(rng.next_u32() as usize % context.line_size))
+});
+
+image.read_image(&input)?;
+image.mosh_with(&pipeline)?;
+# Ok::<(), MoshError>(())
+```
+
+[`MoshCore::mosh_with`]: crate::MoshCore::mosh_with
+*/
+#[derive(Clone, Default)]
+pub struct MoshPipeline {
+    /// Effects, in order.
+    pub stages: Vec<MoshStage>,
+}
+
+impl MoshPipeline {
+    /// Creates a new, empty pipeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a pipeline with built-in effects, using chances from `options`.
+    ///
+    /// Pixel sorting, RGB split, waves, macroblocks, smears, stride skew and byte mutations
+    /// are only added when enabled.
+    ///
+    /// This is a partial preset: bitcrush, chunk copy, swap and tile and `color_space` are
+    /// left out, chunk placement and orientation are still taken from options by
+    /// [`MoshCore::mosh_with`]. Stages draw from their own random streams, so outputs
+    /// differ from [`MoshCore::mosh`] with the same seed.
+    ///
+    /// [`MoshCore::mosh`]: crate::MoshCore::mosh
+    /// [`MoshCore::mosh_with`]: crate::MoshCore::mosh_with
+    pub fn from_options(options: &MoshOptions) -> Self {
+        let shift_unit = options.shift_unit;
+        let bit_flips = options.bit_flip_intensity;
+        let replaced = usize::from(options.byte_replace_intensity);
+        let inserted = options.byte_insert_intensity;
+        let deleted = usize::from(options.byte_delete_intensity);
+        let (sort_key, sort_runs) = (options.sort_key, options.sort_runs);
+        let (sort_min, sort_max) = (options.sort_min, options.sort_max);
+        let sort_interval = cmp::max(usize::from(options.sort_interval), 1);
+        let split_range = i32::from(options.rgb_split_range);
+        let split_offsets = options.rgb_split_offsets.clone();
+        let split_edge = options.rgb_split_edge;
+        let wave_shape = options.wave_shape;
+        let wave_amplitude = options.wave_amplitude;
+        let wave_period = cmp::max(usize::from(options.wave_period), 1);
+        let wave_phase = usize::from(options.wave_phase);
+        let (block_size, block_displacement) = (options.block_size, options.block_displacement);
+        let smear_options = options.clone();
+        let skew_options = options.clone();
+
+        let mut pipeline = Self::new()
+            .line(options.channel_shift, move |rng, context| {
+                let channel_count = context.channel_count;
+                let amount = Uniform::from(0..context.line_size).sample(rng) / channel_count;
+                let amount = shift_unit.align(amount, channel_count);
+                let channel = Uniform::from(0..channel_count).sample(rng);
+                Box::new(MoshLine::ChannelShift(amount, channel, channel_count))
+            })
+            .line(options.line_shift, move |rng, context| {
+                let amount = Uniform::from(0..context.line_size).sample(rng);
+                let amount = shift_unit.align(amount, context.channel_count);
+                Box::new(MoshLine::Shift(amount))
+            })
+            .line(options.reverse, |_, _| Box::new(MoshLine::Reverse))
+            .chunk(options.channel_swap, |rng, context| {
+                let channel_count = context.channel_count;
+                let channel_distrib = Uniform::from(0..channel_count);
+                let channel_1 = channel_distrib.sample(rng);
+                let channel_2 = channel_distrib.sample(rng);
+                Box::new(MoshChunk::ChannelSwap(channel_1, channel_2, channel_count))
+            })
+            .chunk(options.flip, |_, _| Box::new(MoshChunk::Flip));
+
+        // Optional effects, sorting runs on each line
+        let optional: [(MoshLevel, f64, MoshSampler); 10] = [
+            (
+                MoshLevel::Line,
+                options.sort,
+                Arc::new(move |rng, context| {
+                    let runs = match sort_runs {
+                        MoshSortMode::Threshold => MoshSortRuns::Threshold(sort_min, sort_max),
+                        MoshSortMode::Interval => MoshSortRuns::Interval(
+                            (0..16).map(|_| rng.gen_range(1..=sort_interval)).collect(),
+                        ),
+                    };
+
+                    let channel_count = context.channel_count;
+                    let key = match sort_key {
+                        MoshSortKey::Channel(channel) => {
+                            MoshSortKey::Channel(cmp::min(channel, channel_count - 1))
+                        }
+                        key => key,
+                    };
+
+                    Box::new(MoshSort {
+                        channel_count,
+                        depth: context.depth,
+                        key,
+                        runs,
+                    })
+                }),
+            ),
+            (
+                MoshLevel::Chunk,
+                options.rgb_split,
+                Arc::new(move |rng, context| {
+                    let channel_count = context.channel_count;
+                    let color_count = match channel_count {
+                        2 | 4 => channel_count - 1,
+                        _ => channel_count,
+                    };
+
+                    let offsets = if split_offsets.is_empty() {
+                        (0..color_count)
+                            .map(|_| {
+                                (
+                                    rng.gen_range(-split_range..=split_range),
+                                    rng.gen_range(-split_range..=split_range),
+                                )
+                            })
+                            .collect()
+                    } else {
+                        split_offsets.iter().copied().take(color_count).collect()
+                    };
+
+                    Box::new(MoshSplit {
+                        line_size: context.line_size,
+                        channel_count,
+                        offsets,
+                        edge: split_edge,
+                    })
+                }),
+            ),
+            (
+                MoshLevel::Chunk,
+                options.wave,
+                Arc::new(move |rng, context| {
+                    let amplitude = i32::from(wave_amplitude);
+                    let noise = match wave_shape {
+                        MoshWaveShape::Noise => {
+                            (0..(context.line_count + wave_phase) / wave_period + 2)
+                                .map(|_| rng.gen_range(-amplitude..=amplitude))
+                                .collect()
+                        }
+                        _ => Vec::new(),
+                    };
+
+                    Box::new(MoshWave {
+                        line_size: context.line_size,
+                        channel_count: context.channel_count,
+                        shape: wave_shape,
+                        amplitude: usize::from(wave_amplitude),
+                        period: wave_period,
+                        phase: wave_phase,
+                        noise,
+                    })
+                }),
+            ),
+            (
+                MoshLevel::Chunk,
+                options.blocks,
+                Arc::new(move |rng, context| {
+                    Box::new(MoshData::plan_blocks(
+                        context.line_size,
+                        context.line_count,
+                        context.channel_count,
+                        block_size,
+                        block_displacement,
+                        rng,
+                    ))
+                }),
+            ),
+            (
+                MoshLevel::Chunk,
+                options.smear,
+                Arc::new(move |rng, context| {
+                    Box::new(MoshData::plan_smear(
+                        context.line_size,
+                        context.line_count,
+                        context.channel_count,
+                        &smear_options,
+                        rng,
+                    ))
+                }),
+            ),
+            (
+                MoshLevel::Chunk,
+                options.skew,
+                Arc::new(move |rng, context| {
+                    let line_size = context.line_size;
+                    let skew = MoshData::plan_skew(line_size, &skew_options, rng);
+                    Box::new(skew.unwrap_or(MoshSkew { line_size, skew: 0 }))
+                }),
+            ),
+            (
+                MoshLevel::Chunk,
+                options.bit_flip,
+                Arc::new(move |rng, context| {
+                    let position_distrib = Uniform::from(0..chunk_size(context));
+                    let (bits, _) = MoshData::sample_bits(context.depth);
+                    let flips = (0..bit_flips)
+                        .map(|_| {
+                            MoshByte::BitFlip(position_distrib.sample(rng), rng.gen_range(0..bits))
+                        })
+                        .collect();
+
+                    Box::new(MoshBytes(flips))
+                }),
+            ),
+            (
+                MoshLevel::Chunk,
+                options.byte_replace,
+                Arc::new(move |rng, context| {
+                    let position = rng.gen_range(0..chunk_size(context));
+                    let (_, mask) = MoshData::sample_bits(context.depth);
+                    let pattern = (0..rng.gen_range(1..=4))
+                        .map(|_| rng.gen::<u8>() & mask)
+                        .collect();
+                    Box::new(MoshByte::Replace(position, replaced, pattern))
+                }),
+            ),
+            (
+                MoshLevel::Chunk,
+                options.byte_insert,
+                Arc::new(move |rng, context| {
+                    let position = rng.gen_range(0..chunk_size(context));
+                    let (_, mask) = MoshData::sample_bits(context.depth);
+                    let bytes = (0..inserted).map(|_| rng.gen::<u8>() & mask).collect();
+                    Box::new(MoshByte::Insert(position, bytes))
+                }),
+            ),
+            (
+                MoshLevel::Chunk,
+                options.byte_delete,
+                Arc::new(move |rng, context| {
+                    let position = rng.gen_range(0..chunk_size(context));
+                    Box::new(MoshByte::Delete(position, deleted))
+                }),
+            ),
+        ];
+
+        pipeline.stages.extend(
+            optional
+                .into_iter()
+                .filter(|(_, chance, _)| *chance > 0.0)
+                .map(|(level, chance, sampler)| MoshStage {
+                    level,
+                    chance,
+                    sampler,
+                }),
+        );
+
+        pipeline
+    }
+
+    /// Appends a line-level effect.
+    #[must_use]
+    pub fn line<F>(self, chance: f64, sampler: F) -> Self
+    where
+        F: Fn(&mut dyn RngCore, &MoshContext) -> Box<dyn MoshEffect> + Send + Sync + 'static,
+    {
+        self.stage(MoshLevel::Line, chance, sampler)
+    }
+
+    /// Appends a chunk-level effect.
+    #[must_use]
+    pub fn chunk<F>(self, chance: f64, sampler: F) -> Self
+    where
+        F: Fn(&mut dyn RngCore, &MoshContext) -> Box<dyn MoshEffect> + Send + Sync + 'static,
+    {
+        self.stage(MoshLevel::Chunk, chance, sampler)
+    }
+
+    /// Checks that all chances are within `0..=1`.
+    ///
+    /// # Errors
+    ///
+    /// * [`InvalidParameters`]: a chance is out of range.
+    ///
+    /// [`InvalidParameters`]: crate::err::MoshError::InvalidParameters
+    pub fn validate(&self) -> Result<(), MoshError> {
+        if self
+            .stages
+            .iter()
+            .any(|stage| !(0.0..=1.0).contains(&stage.chance))
+        {
+            return Err(MoshError::InvalidParameters {
+                field: "chance",
+                range: String::from("0..=1"),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn stage<F>(mut self, level: MoshLevel, chance: f64, sampler: F) -> Self
+    where
+        F: Fn(&mut dyn RngCore, &MoshContext) -> Box<dyn MoshEffect> + Send + Sync + 'static,
+    {
+        self.stages.push(MoshStage {
+            level,
+            chance,
+            sampler: Arc::new(sampler),
+        });
+
+        self
+    }
+}
+
+// Several bit flips as a single effect
+struct MoshBytes(Vec<MoshByte>);
+
+impl<T> Mosh<T> for MoshBytes
+where
+    MoshByte: Mosh<T>,
+{
+    fn glitch(&self, chunk: &mut [T]) {
+        for byte in &self.0 {
+            byte.glitch(chunk);
+        }
+    }
+}
+
+fn chunk_size(context: &MoshContext) -> usize {
+    (context.line_size * context.line_count).max(1)
+}
@@ -0,0 +1,124 @@
+//! Recipe files
+
+use serde::{Deserialize, Serialize};
+
+use std::{fs, path::Path};
+
+use crate::{err::MoshError, MoshOptions, MoshPlan};
+
+/// Current recipe format version.
+pub const RECIPE_VERSION: u32 = 1;
+
+/// Processing recipe.
+///
+/// Stores [options] and, optionally, a [plan] to reproduce a result exactly.
+///
+/// [options]: MoshOptions
+/// [plan]: MoshPlan
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoshRecipe {
+    /// Format version.
+    pub version: u32,
+    /// Processing options.
+    pub options: MoshOptions,
+    /// Processing plan, it takes precedence over `options` when present.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<MoshPlan>,
+}
+
+impl MoshRecipe {
+    /// Creates a new recipe of the current version.
+    pub fn new(options: MoshOptions, plan: Option<MoshPlan>) -> Self {
+        Self {
+            version: RECIPE_VERSION,
+            options,
+            plan,
+        }
+    }
+}
+
+/// Reads a recipe file
+///
+/// Files with `.json` extension are parsed as JSON, others as TOML.
+///
+/// # Errors
+///
+/// It may fail if the recipe is invalid, its version is not supported or due I/O error.
+pub fn read_recipe(file: impl AsRef<Path>) -> Result<MoshRecipe, MoshError> {
+    let input = fs::read_to_string(&file)?;
+    let recipe: MoshRecipe = if is_json(file.as_ref()) {
+        serde_json::from_str(&input).map_err(|e| MoshError::RecipeError(e.to_string()))?
+    } else {
+        toml::from_str(&input).map_err(|e| MoshError::RecipeError(e.to_string()))?
+    };
+
+    if recipe.version > RECIPE_VERSION {
+        return Err(MoshError::RecipeError(format!(
+            "Unsupported recipe version {}",
+            recipe.version
+        )));
+    }
+
+    Ok(recipe)
+}
+
+/// Writes a recipe file
+///
+/// Files with `.json` extension are written as JSON, others as TOML.
+///
+/// # Errors
+///
+/// It may fail if the recipe can not be serialized or due I/O error.
+pub fn write_recipe(dest: impl AsRef<Path>, recipe: &MoshRecipe) -> Result<(), MoshError> {
+    let output = if is_json(dest.as_ref()) {
+        serde_json::to_string_pretty(recipe).map_err(|e| MoshError::RecipeError(e.to_string()))?
+    } else {
+        toml::to_string(recipe).map_err(|e| MoshError::RecipeError(e.to_string()))?
+    };
+
+    fs::write(dest, output)?;
+
+    Ok(())
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+// TOML integers are signed, so seeds are stored as strings
+pub(crate) mod seed {
+    use serde::{de, Deserializer, Serializer};
+
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(seed)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        struct SeedVisitor;
+
+        impl de::Visitor<'_> for SeedVisitor {
+            type Value = u64;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an unsigned integer or a string")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+                Ok(value)
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<u64, E> {
+                u64::try_from(value).map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(SeedVisitor)
+    }
+}
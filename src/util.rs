@@ -1,11 +1,27 @@
 use adler::adler32;
 use png::{BitDepth, ColorType};
 
-use std::{fs::File, io::BufReader};
+use std::{
+    fs::File,
+    io::BufReader,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use super::{
-    ops::{read_file, write_file},
-    MoshCore,
+    filter::{filter, unfilter},
+    fx::{
+        Mosh, MoshBlockOp, MoshBlocks, MoshByte, MoshChunk, MoshColor, MoshCrush, MoshDither,
+        MoshEdge, MoshLine, MoshRegion, MoshSkew, MoshSmear, MoshSort, MoshSortKey, MoshSortRuns,
+        MoshSplit, MoshWave,
+    },
+    ops::{read_file, write_file, write_indexed_file},
+    pipeline::MoshPipeline,
+    IndexedMode, MoshBlend, MoshChunkPlan, MoshColorSpace, MoshCore, MoshData, MoshFilterType,
+    MoshOptions, MoshOrientation, MoshPlan, MoshShiftUnit, MoshSize, MoshSkewDirection,
+    MoshSortMode, MoshWaveShape,
 };
 
 #[test]
@@ -100,6 +116,120 @@ fn grayscale_alpha() {
     assert_eq!(checksum, 3_326_620_465);
 }
 
+#[test]
+fn rgb_16() {
+    let input = read_file("src/util/test-rgb-16.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+    image.mosh().unwrap();
+    write_file(
+        "moshed-rgb-16.png",
+        &image.data.buf,
+        image.data.width,
+        image.data.height,
+        image.data.color_type,
+        image.data.bit_depth,
+    )
+    .unwrap();
+
+    let output = File::open("moshed-rgb-16.png").unwrap();
+    let mut file = BufReader::new(output);
+    let checksum = adler32(&mut file).unwrap();
+
+    assert_eq!(image.data.bit_depth, BitDepth::Sixteen);
+    assert_eq!(checksum, 1_481_930_586);
+}
+
+#[test]
+fn grayscale_alpha_16() {
+    let input = read_file("src/util/test-grayscale-alpha-16.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+    image.mosh().unwrap();
+    write_file(
+        "moshed-grayscale-alpha-16.png",
+        &image.data.buf,
+        image.data.width,
+        image.data.height,
+        image.data.color_type,
+        image.data.bit_depth,
+    )
+    .unwrap();
+
+    let output = File::open("moshed-grayscale-alpha-16.png").unwrap();
+    let mut file = BufReader::new(output);
+    let checksum = adler32(&mut file).unwrap();
+
+    assert_eq!(image.data.bit_depth, BitDepth::Sixteen);
+    assert_eq!(checksum, 1_188_815_688);
+}
+
+#[test]
+fn grayscale_4() {
+    let input = read_file("src/util/test-grayscale-4.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+    image.mosh().unwrap();
+    write_file(
+        "moshed-grayscale-4.png",
+        &image.data.buf,
+        image.data.width,
+        image.data.height,
+        image.data.color_type,
+        image.data.bit_depth,
+    )
+    .unwrap();
+
+    let output = File::open("moshed-grayscale-4.png").unwrap();
+    let mut file = BufReader::new(output);
+    let checksum = adler32(&mut file).unwrap();
+
+    assert_eq!(image.data.bit_depth, BitDepth::Four);
+    assert_eq!(checksum, 3_944_713_868);
+}
+
+#[test]
+fn grayscale_1() {
+    let input = read_file("src/util/test-grayscale-1.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+    image.mosh().unwrap();
+    write_file(
+        "moshed-grayscale-1.png",
+        &image.data.buf,
+        image.data.width,
+        image.data.height,
+        image.data.color_type,
+        image.data.bit_depth,
+    )
+    .unwrap();
+
+    let output = File::open("moshed-grayscale-1.png").unwrap();
+    let mut file = BufReader::new(output);
+    let checksum = adler32(&mut file).unwrap();
+
+    assert_eq!(image.data.bit_depth, BitDepth::One);
+    assert_eq!(checksum, 3_292_113_111);
+}
+
+#[test]
+fn sub_byte_unchanged() {
+    let input = read_file("src/util/test-grayscale-4.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    image.options.pixelation = 1;
+    image.options.line_shift = 0.0;
+    image.options.reverse = 0.0;
+    image.options.flip = 0.0;
+    image.options.channel_swap = 0.0;
+    image.options.channel_shift = 0.0;
+
+    image.mosh().unwrap();
+
+    assert_eq!(image.data.buf, image.data.image);
+}
+
 #[test]
 fn seed() {
     let mut image = MoshCore::default();
@@ -110,12 +240,164 @@ fn seed() {
 }
 
 #[test]
-#[should_panic(expected = "UnsupportedColorType")]
+fn deterministic() {
+    for file in [
+        "src/util/test-rgb-alpha.png",
+        "src/util/test-rgb-16.png",
+        "src/util/test-grayscale-4.png",
+        "src/util/test-indexed.png",
+    ] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        // Partial chances, effects apply to some chunks only
+        image.options.pixelation = 1;
+        image.options.color_space = MoshColorSpace::Hsv;
+        image.options.bit_flip = 0.5;
+        image.options.byte_replace = 0.5;
+        image.options.byte_insert = 0.5;
+        image.options.byte_delete = 0.5;
+        image.options.sort = 0.5;
+        image.options.rgb_split = 0.5;
+        image.options.wave = 0.5;
+        image.options.blocks = 0.5;
+        image.options.smear = 0.5;
+        image.options.smear_partial = true;
+        image.options.chunk_copy = 0.5;
+        image.options.chunk_swap = 0.5;
+        image.options.chunk_tile = 0.5;
+        image.options.crush = 0.5;
+        image.options.skew = 0.5;
+        image.options.filter_glitch = 10;
+        image.options.filter_mismatch = 0.5;
+        image.options.deflate_glitch = 10;
+        image.options.preserve_alpha = true;
+        image.options.mix = 0.5;
+
+        let mut smeared = Vec::new();
+
+        for seed in 0..4 {
+            image.options.seed = seed;
+            image.mosh().unwrap();
+
+            let moshed = image.data.buf.clone();
+            let plan = image.plan().unwrap();
+
+            image.mosh().unwrap();
+
+            assert_eq!(image.data.buf, moshed);
+            assert_eq!(image.plan().unwrap(), plan);
+
+            let pipeline = MoshPipeline::from_options(&image.options);
+            image.mosh_with(&pipeline).unwrap();
+
+            let piped = image.data.buf.clone();
+            image.mosh_with(&pipeline).unwrap();
+
+            assert_eq!(image.data.buf, piped);
+
+            smeared.extend(plan.chunks.iter().map(|chunk| chunk.smear.is_some()));
+        }
+
+        assert!(smeared.contains(&true));
+        assert!(smeared.contains(&false));
+    }
+}
+
+#[test]
 fn indexed() {
     let input = read_file("src/util/test-indexed.png").unwrap();
     let mut image = MoshCore::new();
     image.read_image(&input).unwrap();
     image.mosh().unwrap();
+    write_file(
+        "moshed-indexed.png",
+        &image.data.buf,
+        image.data.width,
+        image.data.height,
+        image.data.color_type,
+        image.data.bit_depth,
+    )
+    .unwrap();
+
+    let output = File::open("moshed-indexed.png").unwrap();
+    let mut file = BufReader::new(output);
+    let checksum = adler32(&mut file).unwrap();
+
+    assert_eq!(image.data.color_type, ColorType::Rgb);
+    assert_eq!(checksum, 3_461_226_371);
+}
+
+#[test]
+fn indexed_palette() {
+    let input = read_file("src/util/test-indexed.png").unwrap();
+    let mut image = MoshCore::new();
+    image.options.indexed = IndexedMode::Both;
+    image.read_image(&input).unwrap();
+    image.mosh().unwrap();
+    write_indexed_file(
+        "moshed-indexed-palette.png",
+        &image.data.buf,
+        image.data.width,
+        image.data.height,
+        image.data.bit_depth,
+        &image.data.palette_buf,
+        image.data.trns_buf.as_deref(),
+    )
+    .unwrap();
+
+    let output = File::open("moshed-indexed-palette.png").unwrap();
+    let mut file = BufReader::new(output);
+    let checksum = adler32(&mut file).unwrap();
+
+    assert_eq!(image.data.color_type, ColorType::Indexed);
+    assert_ne!(image.data.palette_buf, image.data.palette);
+    assert_eq!(checksum, 1_245_742_045);
+}
+
+#[test]
+fn indexed_trns() {
+    let input = read_file("src/util/test-indexed.png").unwrap();
+    let mut image = MoshCore::new();
+    image.options.indexed = IndexedMode::Palette;
+    image.options.line_shift = 1.0;
+    image.options.reverse = 1.0;
+    image.options.channel_shift = 0.0;
+    image.options.channel_swap = 0.0;
+    image.read_image(&input).unwrap();
+
+    // Gray entries tell where each one moved, the last ones are missing from `tRNS`
+    let entry_count = image.data.palette.len() / 3;
+    image.data.palette = (0..entry_count).flat_map(|i| [i as u8; 3]).collect();
+    image.data.trns = Some((0..entry_count / 2).map(|i| i as u8).collect());
+    image.mosh().unwrap();
+
+    let trns = image.data.trns_buf.clone().unwrap();
+    let moved = (0..entry_count).map(|i| {
+        let entry = usize::from(image.data.palette_buf[i * 3]);
+        if entry < entry_count / 2 {
+            entry as u8
+        } else {
+            0xFF
+        }
+    });
+
+    assert_ne!(image.data.palette_buf, image.data.palette);
+    assert_eq!(trns.len(), entry_count);
+    assert!(trns.iter().copied().eq(moved));
+    assert_eq!(image.data.trns.as_ref().unwrap().len(), entry_count / 2);
+}
+
+#[test]
+#[should_panic(expected = "UnsupportedColorType")]
+fn indexed_not_expanded() {
+    let input = read_file("src/util/test-indexed.png").unwrap();
+    let mut image = MoshCore::new();
+    image.options.indexed = IndexedMode::Palette;
+    image.read_image(&input).unwrap();
+    image.options.indexed = IndexedMode::Expand;
+    image.mosh().unwrap();
 }
 
 #[test]
@@ -150,3 +432,1482 @@ fn invalid_parameters() {
 
     image.mosh().unwrap();
 }
+
+#[test]
+#[should_panic(expected = "InvalidParameters")]
+fn invalid_chance() {
+    let input = read_file("src/util/test-grayscale.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    image.options.flip = 1.5;
+
+    image.mosh().unwrap();
+}
+
+#[test]
+#[should_panic(expected = "InvalidParameters")]
+fn invalid_pixelation() {
+    let input = read_file("src/util/test-pixelation.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    image.options.pixelation = 11;
+
+    image.mosh().unwrap();
+}
+
+#[test]
+fn validate() {
+    let mut image = MoshCore::new();
+
+    assert!(image.options.validate().is_ok());
+
+    image.options.channel_shift = f64::NAN;
+
+    assert_eq!(
+        image.options.validate().unwrap_err().to_string(),
+        "Invalid `channel_shift` value, allowed range is 0..=1"
+    );
+}
+
+#[test]
+fn plan() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+    image.mosh().unwrap();
+
+    let moshed = image.data.buf.clone();
+    let plan = image.plan().unwrap();
+
+    image.apply(&MoshPlan::default()).unwrap();
+
+    assert_eq!(image.data.buf, image.data.image);
+
+    image.apply(&plan).unwrap();
+
+    assert!(!plan.chunks.is_empty());
+    assert_eq!(plan, image.plan().unwrap());
+    assert_eq!(image.data.buf, moshed);
+}
+
+#[test]
+fn plan_replay() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    let plan = image.plan().unwrap();
+    let input = read_file("src/util/test-pixelation.png").unwrap();
+    image.read_image(&input).unwrap();
+    image.apply(&plan).unwrap();
+
+    assert_eq!(image.data.buf.len(), image.data.image.len());
+}
+
+#[test]
+#[should_panic(expected = "InvalidParameters")]
+fn invalid_plan() {
+    let input = read_file("src/util/test-grayscale.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    let plan = MoshPlan {
+        chunks: vec![MoshChunkPlan {
+            first_line: 0,
+            last_line: 10,
+            columns: None,
+            vertical: false,
+            color: None,
+            lines: Vec::new(),
+            chunk: vec![MoshChunk::ChannelSwap(0, 3, 3)],
+            sort: None,
+            split: None,
+            wave: None,
+            blocks: None,
+            smear: None,
+            crush: None,
+            region: None,
+            skew: None,
+            bytes: Vec::new(),
+        }],
+        ..MoshPlan::default()
+    };
+
+    image.apply(&plan).unwrap();
+}
+
+#[test]
+fn extreme_plan() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    let line_size = image.data.width as usize * 3;
+    let chunk = MoshChunkPlan {
+        first_line: 0,
+        last_line: usize::MAX,
+        lines: vec![
+            MoshLine::ChannelShift(usize::MAX, 2, 3),
+            MoshLine::Shift(usize::MAX),
+        ],
+        sort: Some(MoshSort {
+            channel_count: 3,
+            depth: 8,
+            key: MoshSortKey::Luminance,
+            runs: MoshSortRuns::Interval(vec![usize::MAX]),
+        }),
+        wave: Some(MoshWave {
+            line_size,
+            channel_count: 3,
+            shape: MoshWaveShape::Noise,
+            amplitude: usize::MAX,
+            period: usize::MAX,
+            phase: usize::MAX,
+            noise: vec![i32::MIN, i32::MAX],
+        }),
+        smear: Some(MoshSmear {
+            line_size,
+            channel_count: 3,
+            vertical: true,
+            source: usize::MAX,
+            length: usize::MAX,
+            span: Some((0, usize::MAX)),
+        }),
+        crush: Some(MoshCrush {
+            line_size,
+            channel_count: 3,
+            depth: 8,
+            bits: vec![u8::MAX, 1],
+            dither: MoshDither::Diffusion,
+        }),
+        region: Some(MoshRegion::Tile(usize::MAX)),
+        skew: Some(MoshSkew {
+            line_size,
+            skew: isize::MAX,
+        }),
+        bytes: vec![
+            MoshByte::Replace(usize::MAX, usize::MAX, vec![1]),
+            MoshByte::Replace(0, usize::MAX, vec![1]),
+            MoshByte::Delete(0, usize::MAX),
+        ],
+        ..MoshChunkPlan::default()
+    };
+
+    let plan = MoshPlan {
+        chunks: vec![
+            chunk.clone(),
+            MoshChunkPlan {
+                skew: Some(MoshSkew {
+                    line_size,
+                    skew: isize::MIN,
+                }),
+                ..chunk
+            },
+        ],
+        ..MoshPlan::default()
+    };
+
+    image.apply(&plan).unwrap();
+
+    MoshCrush {
+        line_size,
+        channel_count: usize::MAX,
+        depth: u8::MAX,
+        bits: vec![1],
+        dither: MoshDither::Ordered,
+    }
+    .glitch(&mut image.data.buf);
+}
+
+#[test]
+fn plan_geometry() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    image.options.wave = 1.0;
+    image.options.crush = 1.0;
+
+    let plan = image.plan().unwrap();
+    image.apply(&plan).unwrap();
+
+    let input = read_file("src/util/test-rgb-16.png").unwrap();
+    image.read_image(&input).unwrap();
+
+    assert_eq!(
+        image.apply(&plan).unwrap_err().to_string(),
+        "Invalid `line_size` value, allowed range is 384"
+    );
+
+    let input = read_file("src/util/test-rgb-alpha.png").unwrap();
+    image.read_image(&input).unwrap();
+
+    assert!(image.apply(&plan).is_err());
+
+    let plan = MoshPlan {
+        preserve_alpha: true,
+        ..plan
+    };
+
+    image.apply(&plan).unwrap();
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn recipe() {
+    use super::recipe::{read_recipe, write_recipe, MoshRecipe};
+
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+    image.options.seed = u64::MAX;
+
+    let recipe = MoshRecipe::new(image.options.clone(), Some(image.plan().unwrap()));
+
+    for file in ["moshed-recipe.toml", "moshed-recipe.json"] {
+        write_recipe(file, &recipe).unwrap();
+
+        let loaded = read_recipe(file).unwrap();
+
+        assert_eq!(loaded.options.seed, u64::MAX);
+        assert_eq!(loaded.plan, recipe.plan);
+    }
+}
+
+#[test]
+fn independent_streams() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    image.options.min_rate = 10;
+    image.options.max_rate = 10;
+    image.options.independent_streams = true;
+
+    let plan_1 = image.plan().unwrap();
+    image.options.flip = 0.31;
+    image.options.line_shift = 0.9;
+    let plan_2 = image.plan().unwrap();
+
+    for (chunk_1, chunk_2) in plan_1.chunks.iter().zip(&plan_2.chunks) {
+        let no_shift = |lines: &[MoshLine]| {
+            lines
+                .iter()
+                .filter(|line| !matches!(line, MoshLine::Shift(_)))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(chunk_1.first_line, chunk_2.first_line);
+        assert_eq!(chunk_1.last_line, chunk_2.last_line);
+        assert_eq!(no_shift(&chunk_1.lines), no_shift(&chunk_2.lines));
+        assert_eq!(
+            chunk_1
+                .chunk
+                .first()
+                .filter(|chunk| **chunk != MoshChunk::Flip),
+            chunk_2
+                .chunk
+                .first()
+                .filter(|chunk| **chunk != MoshChunk::Flip)
+        );
+    }
+
+    assert_ne!(plan_1, plan_2);
+}
+
+#[test]
+fn pipeline() {
+    struct Fill;
+
+    impl Mosh<u8> for Fill {
+        fn glitch(&self, value: &mut [u8]) {
+            value.fill(0);
+        }
+    }
+
+    impl Mosh<u16> for Fill {
+        fn glitch(&self, value: &mut [u16]) {
+            value.fill(0);
+        }
+    }
+
+    for file in ["src/util/test-rgb.png", "src/util/test-rgb-16.png"] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+        image.options.pixelation = 1;
+
+        image.mosh_with(&MoshPipeline::new()).unwrap();
+
+        assert_eq!(image.data.buf, image.data.image);
+
+        let pipeline = MoshPipeline::from_options(&image.options).chunk(1.0, |_, _| Box::new(Fill));
+        image.mosh_with(&pipeline).unwrap();
+        let moshed = image.data.buf.clone();
+        image.mosh_with(&pipeline).unwrap();
+
+        assert_eq!(image.data.buf, moshed);
+        assert!(image.data.buf.contains(&0));
+        assert_ne!(image.data.buf, image.data.image);
+    }
+}
+
+#[test]
+fn pipeline_options() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    image.options.min_rate = 32;
+    image.options.max_rate = 32;
+    image.options.independent_streams = true;
+    image.options.line_shift = 0.0;
+    image.options.channel_swap = 0.0;
+    image.options.channel_shift = 0.0;
+    image.options.reverse = 0.5;
+    image.options.flip = 0.5;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let probe = |options: &MoshOptions| {
+        let count = Arc::clone(&count);
+
+        MoshPipeline::from_options(options).chunk(0.5, move |_, _| {
+            count.fetch_add(1, Ordering::Relaxed);
+            Box::new(MoshLine::Reverse)
+        })
+    };
+
+    image.mosh_with(&probe(&image.options)).unwrap();
+
+    let moshed = image.data.buf.clone();
+    let sampled = count.swap(0, Ordering::Relaxed);
+
+    assert!((1..32).contains(&sampled));
+    assert_ne!(moshed, image.data.image);
+
+    image.mosh_with(&probe(&image.options)).unwrap();
+
+    assert_eq!(image.data.buf, moshed);
+    assert_eq!(count.swap(0, Ordering::Relaxed), sampled);
+
+    // Other stages do not shift the draws of the probe
+    image.options.reverse = 0.9;
+    image.mosh_with(&probe(&image.options)).unwrap();
+
+    assert_ne!(image.data.buf, moshed);
+    assert_eq!(count.swap(0, Ordering::Relaxed), sampled);
+}
+
+#[test]
+#[should_panic(expected = "InvalidParameters")]
+fn invalid_pipeline() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    let pipeline = MoshPipeline::new().line(1.5, |_, _| Box::new(MoshLine::Reverse));
+    image.mosh_with(&pipeline).unwrap();
+}
+
+#[test]
+fn chunk_geometry() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    image.options.min_rate = 1;
+    image.options.max_rate = 1;
+    image.options.pixelation = 1;
+    image.options.flip = 1.0;
+    image.options.min_chunk_height = Some(MoshSize::Pixels(5));
+    image.options.max_chunk_height = Some(MoshSize::Pixels(5));
+    image.options.min_chunk_width = "50%".parse().ok();
+    image.options.max_chunk_width = "50%".parse().ok();
+
+    let plan = image.plan().unwrap();
+    let chunk = &plan.chunks[0];
+    let (first_column, last_column) = chunk.columns.unwrap();
+    let line_size = image.data.line_size;
+
+    assert_eq!(chunk.last_line - chunk.first_line, 5);
+    assert_eq!(
+        last_column - first_column,
+        image.data.width as usize / 2 * 3
+    );
+
+    image.apply(&plan).unwrap();
+
+    for (index, (moshed, original)) in image.data.buf.iter().zip(&image.data.image).enumerate() {
+        let line = index / line_size;
+        let column = index % line_size;
+
+        if !(chunk.first_line..chunk.last_line).contains(&line)
+            || !(first_column..last_column).contains(&column)
+        {
+            assert_eq!(moshed, original);
+        }
+    }
+
+    assert_ne!(image.data.buf, image.data.image);
+}
+
+#[test]
+fn size() {
+    assert_eq!("40".parse::<MoshSize>().unwrap(), MoshSize::Pixels(40));
+    assert_eq!("25%".parse::<MoshSize>().unwrap(), MoshSize::Fraction(0.25));
+    assert_eq!(MoshSize::Fraction(0.25).to_string(), "25%");
+    assert!("150%".parse::<MoshSize>().is_err());
+    assert!("-1".parse::<MoshSize>().is_err());
+}
+
+#[test]
+fn vertical() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    let plan = MoshPlan {
+        chunks: vec![MoshChunkPlan {
+            first_line: 0,
+            last_line: image.data.width as usize,
+            columns: None,
+            vertical: true,
+            color: None,
+            lines: vec![MoshLine::Reverse],
+            chunk: Vec::new(),
+            sort: None,
+            split: None,
+            wave: None,
+            blocks: None,
+            smear: None,
+            crush: None,
+            region: None,
+            skew: None,
+            bytes: Vec::new(),
+        }],
+        ..MoshPlan::default()
+    };
+
+    image.apply(&plan).unwrap();
+
+    // Reverse also swaps channels within each pixel
+    let line_size = image.data.line_size;
+    let moshed = image.data.buf.chunks_exact(line_size);
+    let original = image.data.image.chunks_exact(line_size).rev();
+
+    for (moshed, original) in moshed.zip(original) {
+        for (moshed, original) in moshed.chunks_exact(3).zip(original.chunks_exact(3)) {
+            assert!(moshed.iter().eq(original.iter().rev()));
+        }
+    }
+
+    image.options.orientation = MoshOrientation::Random;
+    image.options.min_rate = 10;
+    image.mosh().unwrap();
+    let moshed = image.data.buf.clone();
+    image.mosh().unwrap();
+
+    assert_eq!(image.data.buf, moshed);
+    assert!(image
+        .plan()
+        .unwrap()
+        .chunks
+        .iter()
+        .any(|chunk| chunk.vertical));
+}
+
+#[test]
+fn pixel_shift() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    image.options.min_rate = 20;
+    image.options.max_rate = 20;
+    image.options.line_shift = 1.0;
+    image.options.channel_shift = 1.0;
+    image.options.shift_unit = MoshShiftUnit::Pixel;
+
+    for line in image
+        .plan()
+        .unwrap()
+        .chunks
+        .iter()
+        .flat_map(|chunk| &chunk.lines)
+    {
+        match line {
+            MoshLine::Shift(amount) | MoshLine::ChannelShift(amount, _, _) => {
+                assert_eq!(amount % 3, 0);
+            }
+            MoshLine::Reverse => {}
+        }
+    }
+}
+
+#[test]
+fn preserve_alpha() {
+    for (file, pixel_size, alpha_size) in [
+        ("src/util/test-rgb-alpha.png", 4, 1),
+        ("src/util/test-grayscale-alpha-16.png", 4, 2),
+    ] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.min_rate = 10;
+        image.options.line_shift = 1.0;
+        image.options.channel_shift = 1.0;
+        image.options.channel_swap = 1.0;
+        image.options.preserve_alpha = true;
+        image.mosh().unwrap();
+
+        let alpha = |buf: &[u8]| -> Vec<u8> {
+            buf.chunks_exact(pixel_size)
+                .flat_map(|pixel| pixel[pixel_size - alpha_size..].to_vec())
+                .collect()
+        };
+
+        assert_eq!(alpha(&image.data.buf), alpha(&image.data.image));
+        assert_ne!(image.data.buf, image.data.image);
+    }
+}
+
+#[test]
+fn blend() {
+    for file in [
+        "src/util/test-rgb-alpha.png",
+        "src/util/test-rgb-16.png",
+        "src/util/test-grayscale-4.png",
+        "src/util/test-indexed.png",
+    ] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.options.indexed = IndexedMode::Both;
+        image.read_image(&input).unwrap();
+
+        image.options.min_rate = 10;
+        image.options.mix = 0.0;
+        image.mosh().unwrap();
+
+        assert_eq!(image.data.buf, image.data.image);
+        assert_eq!(image.data.palette_buf, image.data.palette);
+
+        image.options.mix = 0.5;
+        let mut results = Vec::new();
+
+        for blend in [
+            MoshBlend::Normal,
+            MoshBlend::Difference,
+            MoshBlend::Screen,
+            MoshBlend::Multiply,
+        ] {
+            image.options.blend = blend;
+            image.mosh().unwrap();
+            results.push(image.data.buf.clone());
+        }
+
+        assert!(results
+            .iter()
+            .all(|buf| buf.len() == image.data.image.len()));
+        assert_ne!(results[0], image.data.image);
+    }
+
+    assert_eq!(MoshBlend::Normal.apply(0, 255, 255, 0.5), 128);
+    assert_eq!(MoshBlend::Difference.apply(200, 50, 255, 1.0), 150);
+    assert_eq!(MoshBlend::Screen.apply(255, 10, 255, 1.0), 255);
+    assert_eq!(MoshBlend::Multiply.apply(255, 100, 255, 1.0), 100);
+}
+
+#[test]
+fn filters() {
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    let filter_types = [
+        MoshFilterType::None,
+        MoshFilterType::Sub,
+        MoshFilterType::Up,
+        MoshFilterType::Average,
+        MoshFilterType::Paeth,
+    ];
+
+    // One filter for all rows, then a different one for each row
+    for filters in filter_types.chunks(1).chain([filter_types.as_slice()]) {
+        let mut data = filter(&image.data.image, image.data.line_size, 3, filters);
+
+        if filters.len() > 1 {
+            assert_ne!(
+                data,
+                filter(&image.data.image, image.data.line_size, 3, &filters[..1])
+            );
+        }
+
+        unfilter(&mut data, image.data.line_size, 3, filters);
+
+        assert_eq!(data, image.data.image);
+    }
+}
+
+#[test]
+fn filter_glitch() {
+    for file in [
+        "src/util/test-rgb.png",
+        "src/util/test-rgb-16.png",
+        "src/util/test-grayscale-1.png",
+    ] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.min_rate = 0;
+        image.options.max_rate = 0;
+        image.options.pixelation = 1;
+        image.options.filter_glitch = 20;
+        image.options.filter_mismatch = 0.0;
+        image.mosh().unwrap();
+
+        let moshed = image.data.buf.clone();
+        let plan = image.plan().unwrap().filter.unwrap();
+
+        assert_eq!(plan.encode, plan.decode);
+        assert_eq!(plan.encode.len(), image.data.height as usize);
+        assert!(plan.encode.iter().any(|filter| *filter != plan.encode[0]));
+        assert_eq!(plan.ops.len(), 20);
+        assert_ne!(moshed, image.data.image);
+
+        // Some rows are decoded with a wrong filter
+        image.options.filter_mismatch = 0.5;
+
+        let plan = image.plan().unwrap().filter.unwrap();
+        let mismatched = plan
+            .encode
+            .iter()
+            .zip(&plan.decode)
+            .filter(|(encode, decode)| encode != decode)
+            .count();
+
+        assert!((1..plan.encode.len()).contains(&mismatched));
+    }
+}
+
+#[test]
+fn filter_glitch_indexed() {
+    let input = read_file("src/util/test-indexed.png").unwrap();
+    let mut image = MoshCore::new();
+    image.options.indexed = IndexedMode::Indices;
+    image.read_image(&input).unwrap();
+
+    // Palette shorter than the index range
+    image.data.palette.truncate(16 * 3);
+
+    for index in &mut image.data.image {
+        *index %= 16;
+    }
+
+    image.options.min_rate = 0;
+    image.options.max_rate = 0;
+    image.options.pixelation = 1;
+    image.options.filter_glitch = 200;
+    image.mosh().unwrap();
+
+    assert_ne!(image.data.buf, image.data.image);
+    assert!(image.data.buf.iter().all(|index| *index < 16));
+}
+
+#[test]
+fn deflate_glitch() {
+    for (file, indexed) in [
+        ("src/util/test-rgb.png", IndexedMode::Expand),
+        ("src/util/test-rgb-alpha.png", IndexedMode::Expand),
+        ("src/util/test-grayscale-1.png", IndexedMode::Expand),
+        ("src/util/test-indexed.png", IndexedMode::Indices),
+    ] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.options.indexed = indexed;
+        image.read_image(&input).unwrap();
+
+        image.options.min_rate = 0;
+        image.options.max_rate = 0;
+        image.options.pixelation = 1;
+        image.options.deflate_glitch = 50;
+
+        for seed in 0..8 {
+            image.options.seed = seed;
+            image.mosh().unwrap();
+
+            let moshed = image.data.buf.clone();
+            let plan = image.plan().unwrap().deflate.unwrap();
+
+            assert_eq!(plan.ops.len(), 50);
+            assert_eq!(moshed.len(), image.data.image.len());
+        }
+    }
+}
+
+#[test]
+fn bytes() {
+    let mut value = [1_u8, 2, 3, 4, 5, 6];
+
+    MoshByte::BitFlip(0, 9).glitch(&mut value);
+    MoshByte::Insert(4, vec![9, 9, 9, 9]).glitch(&mut value);
+
+    assert_eq!(value, [3, 2, 3, 4, 9, 9]);
+
+    MoshByte::Delete(1, 2).glitch(&mut value);
+
+    assert_eq!(value, [3, 4, 9, 9, 0, 0]);
+
+    MoshByte::Replace(4, 8, vec![7]).glitch(&mut value);
+
+    assert_eq!(value, [3, 4, 9, 9, 7, 7]);
+
+    let mut value = [0_u16; 3];
+
+    MoshByte::Replace(1, 1, vec![1]).glitch(&mut value);
+    MoshByte::BitFlip(2, 15).glitch(&mut value);
+    MoshByte::Delete(9, 9).glitch(&mut value);
+
+    assert_eq!(value, [0, 257, 32_768]);
+
+    for file in ["src/util/test-rgb.png", "src/util/test-grayscale-4.png"] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.pixelation = 1;
+        image.options.bit_flip = 1.0;
+        image.options.byte_replace = 1.0;
+        image.options.byte_insert = 1.0;
+        image.options.byte_delete = 1.0;
+        image.mosh().unwrap();
+
+        let moshed = image.data.buf.clone();
+        let plan = image.plan().unwrap();
+
+        assert!(plan.chunks.iter().all(|chunk| chunk.bytes.len() == 11));
+        assert_eq!(moshed.len(), image.data.image.len());
+        assert_ne!(moshed, image.data.image);
+    }
+}
+
+#[test]
+fn bytes_sub_byte() {
+    for (file, depth) in [
+        ("src/util/test-grayscale-1.png", 1),
+        ("src/util/test-grayscale-4.png", 4),
+    ] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.pixelation = 1;
+        image.options.line_shift = 0.0;
+        image.options.reverse = 0.0;
+        image.options.flip = 0.0;
+        image.options.channel_shift = 0.0;
+        image.options.channel_swap = 0.0;
+        image.options.bit_flip = 1.0;
+        image.options.byte_replace = 1.0;
+        image.options.byte_insert = 1.0;
+        image.mosh().unwrap();
+
+        let plan = image.plan().unwrap();
+
+        // Flips and written values stay within the sample
+        for byte in plan.chunks.iter().flat_map(|chunk| &chunk.bytes) {
+            match byte {
+                MoshByte::BitFlip(_, bit) => assert!(*bit < depth),
+                MoshByte::Replace(_, _, values) | MoshByte::Insert(_, values) => {
+                    assert!(values.iter().all(|value| *value < 1 << depth));
+                }
+                MoshByte::Delete(..) => {}
+            }
+        }
+
+        assert_ne!(image.data.buf, image.data.image);
+
+        // Flips alone change the image
+        image.options.byte_replace = 0.0;
+        image.options.byte_insert = 0.0;
+        image.mosh().unwrap();
+
+        assert_ne!(image.data.buf, image.data.image);
+    }
+}
+
+#[test]
+fn bytes_indexed() {
+    for indexed in [IndexedMode::Indices, IndexedMode::Both] {
+        let input = read_file("src/util/test-indexed.png").unwrap();
+        let mut image = MoshCore::new();
+        image.options.indexed = indexed;
+        image.read_image(&input).unwrap();
+
+        // Palette shorter than the index range
+        image.data.palette.truncate(16 * 3);
+
+        for index in &mut image.data.image {
+            *index %= 16;
+        }
+
+        image.options.pixelation = 1;
+        image.options.bit_flip = 1.0;
+        image.options.byte_replace = 1.0;
+        image.options.byte_insert = 1.0;
+        image.mosh().unwrap();
+
+        assert_ne!(image.data.buf, image.data.image);
+        assert!(image.data.buf.iter().all(|index| *index < 16));
+    }
+}
+
+#[test]
+fn sort() {
+    let mut value = [9_u8, 100, 80, 200, 70, 1, 90];
+
+    MoshSort {
+        channel_count: 1,
+        depth: 8,
+        key: MoshSortKey::Luminance,
+        runs: MoshSortRuns::Threshold(64, 224),
+    }
+    .glitch(&mut value);
+
+    assert_eq!(value, [9, 70, 80, 100, 200, 1, 90]);
+
+    let mut value = [30_u8, 0, 0, 0, 0, 255, 10, 0, 0, 0, 20, 0];
+
+    let sort = MoshSort {
+        channel_count: 3,
+        depth: 8,
+        key: MoshSortKey::Hue,
+        runs: MoshSortRuns::Interval(vec![0, 2]),
+    };
+
+    for line in value.chunks_exact_mut(6) {
+        sort.glitch(line);
+    }
+
+    assert_eq!(value, [30, 0, 0, 0, 0, 255, 10, 0, 0, 0, 20, 0]);
+
+    let sort = MoshSort {
+        channel_count: 3,
+        depth: 8,
+        key: MoshSortKey::Channel(0),
+        runs: MoshSortRuns::Interval(vec![2]),
+    };
+
+    for line in value.chunks_exact_mut(6) {
+        sort.glitch(line);
+    }
+
+    assert_eq!(value, [0, 0, 255, 30, 0, 0, 0, 20, 0, 10, 0, 0]);
+
+    // Keys of sub-byte samples are scaled up to the thresholds
+    for (depth, runs, mut value, expected) in [
+        (
+            4,
+            MoshSortRuns::Threshold(64, 224),
+            [12_u8, 6, 4, 9, 15],
+            [4, 6, 9, 12, 15],
+        ),
+        (
+            1,
+            MoshSortRuns::Threshold(0, 255),
+            [1, 0, 1, 0, 1],
+            [0, 0, 1, 1, 1],
+        ),
+    ] {
+        MoshSort {
+            channel_count: 1,
+            depth,
+            key: MoshSortKey::Luminance,
+            runs,
+        }
+        .glitch(&mut value);
+
+        assert_eq!(value, expected);
+    }
+
+    for file in [
+        "src/util/test-grayscale-1.png",
+        "src/util/test-grayscale-4.png",
+    ] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.pixelation = 1;
+        image.options.sort = 1.0;
+        image.options.sort_min = 0;
+        image.mosh().unwrap();
+
+        assert_ne!(image.data.buf, image.data.image);
+    }
+
+    for file in [
+        "src/util/test-grayscale.png",
+        "src/util/test-grayscale-alpha.png",
+        "src/util/test-rgb.png",
+        "src/util/test-rgb-alpha.png",
+    ] {
+        for runs in [MoshSortMode::Threshold, MoshSortMode::Interval] {
+            let input = read_file(file).unwrap();
+            let mut image = MoshCore::new();
+            image.read_image(&input).unwrap();
+
+            image.options.pixelation = 1;
+            image.options.sort = 1.0;
+            image.options.sort_runs = runs;
+            image.mosh().unwrap();
+
+            let moshed = image.data.buf.clone();
+
+            assert!(image
+                .plan()
+                .unwrap()
+                .chunks
+                .iter()
+                .all(|chunk| chunk.sort.is_some()));
+            assert_ne!(moshed, image.data.image);
+        }
+    }
+}
+
+#[test]
+fn rgb_split() {
+    for (edge, offset, expected) in [
+        (MoshEdge::Clamp, (1, 0), [1, 1, 2, 4, 4, 5]),
+        (MoshEdge::Wrap, (1, 0), [3, 1, 2, 6, 4, 5]),
+        (MoshEdge::Wrap, (0, 1), [4, 5, 6, 1, 2, 3]),
+        (MoshEdge::Transparent, (1, 0), [0, 1, 2, 0, 4, 5]),
+    ] {
+        let mut value = [1_u8, 2, 3, 4, 5, 6];
+
+        MoshSplit {
+            line_size: 3,
+            channel_count: 1,
+            offsets: vec![offset],
+            edge,
+        }
+        .glitch(&mut value);
+
+        assert_eq!(value, expected);
+    }
+
+    let mut value = [10_u16, 65_535, 20, 65_535];
+
+    MoshSplit {
+        line_size: 4,
+        channel_count: 2,
+        offsets: vec![(1, 0)],
+        edge: MoshEdge::Transparent,
+    }
+    .glitch(&mut value);
+
+    assert_eq!(value, [0, 0, 10, 65_535]);
+
+    for file in ["src/util/test-rgb.png", "src/util/test-rgb-alpha.png"] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.pixelation = 1;
+        image.options.rgb_split = 1.0;
+        image.options.rgb_split_offsets = vec![(-4, 0), (0, 2), (4, 0), (9, 9)];
+        image.mosh().unwrap();
+
+        let plan = image.plan().unwrap();
+
+        assert!(plan
+            .chunks
+            .iter()
+            .all(|chunk| chunk.split.as_ref().unwrap().offsets == [(-4, 0), (0, 2), (4, 0)]));
+        assert_ne!(image.data.buf, image.data.image);
+    }
+}
+
+#[test]
+fn wave() {
+    let line = [0_u8, 1, 2, 3];
+    let rotated = [line, [3, 0, 1, 2], line, [1, 2, 3, 0]].concat();
+
+    for shape in [MoshWaveShape::Sine, MoshWaveShape::Triangle] {
+        let mut value = line.repeat(4);
+
+        MoshWave {
+            line_size: 4,
+            channel_count: 1,
+            shape,
+            amplitude: 1,
+            period: 4,
+            phase: 0,
+            noise: Vec::new(),
+        }
+        .glitch(&mut value);
+
+        assert_eq!(value, rotated);
+    }
+
+    let mut value = line.repeat(3);
+
+    MoshWave {
+        line_size: 4,
+        channel_count: 1,
+        shape: MoshWaveShape::Noise,
+        amplitude: 2,
+        period: 2,
+        phase: 0,
+        noise: vec![0, 2],
+    }
+    .glitch(&mut value);
+
+    assert_eq!(value, [[0, 1, 2, 3], [3, 0, 1, 2], [2, 3, 0, 1]].concat());
+
+    for shape in [
+        MoshWaveShape::Sine,
+        MoshWaveShape::Triangle,
+        MoshWaveShape::Noise,
+    ] {
+        let input = read_file("src/util/test-rgb.png").unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.pixelation = 1;
+        image.options.wave = 1.0;
+        image.options.wave_shape = shape;
+        image.mosh().unwrap();
+
+        let moshed = image.data.buf.clone();
+
+        assert!(image
+            .plan()
+            .unwrap()
+            .chunks
+            .iter()
+            .all(|chunk| chunk.wave.is_some()));
+        assert_ne!(moshed, image.data.image);
+    }
+}
+
+#[test]
+fn blocks() {
+    let value: Vec<u8> = (0..16).collect();
+
+    for (op, expected) in [
+        (
+            MoshBlockOp::Swap((0, 0), (1, 1)),
+            [10, 11, 2, 3, 14, 15, 6, 7, 8, 9, 0, 1, 12, 13, 4, 5],
+        ),
+        (
+            MoshBlockOp::Duplicate((1, 0), (0, 1)),
+            [0, 1, 2, 3, 4, 5, 6, 7, 2, 3, 10, 11, 6, 7, 14, 15],
+        ),
+        (
+            MoshBlockOp::Displace((0, 0), 1, 1),
+            [5, 6, 2, 3, 9, 10, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+        ),
+        (
+            MoshBlockOp::Displace((7, 7), 4, 4),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 15, 15, 12, 13, 15, 15],
+        ),
+    ] {
+        let mut value = value.clone();
+
+        MoshBlocks {
+            line_size: 4,
+            channel_count: 1,
+            size: 2,
+            ops: vec![op],
+        }
+        .glitch(&mut value);
+
+        assert_eq!(value, expected);
+    }
+
+    for file in [
+        "src/util/test-rgb.png",
+        "src/util/test-grayscale-alpha-16.png",
+    ] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.pixelation = 1;
+        image.options.min_chunk_height = Some(MoshSize::Pixels(32));
+        image.options.blocks = 1.0;
+        image.options.block_size = 8;
+        image.options.block_displacement = 0.5;
+        image.mosh().unwrap();
+
+        let moshed = image.data.buf.clone();
+        let plan = image.plan().unwrap();
+
+        assert!(plan.chunks.iter().all(|chunk| chunk.blocks.is_some()));
+        assert_ne!(moshed, image.data.image);
+    }
+}
+
+#[test]
+fn smear() {
+    let value: Vec<u8> = (0..12).collect();
+
+    for (vertical, source, length, span, expected) in [
+        (true, 1, 2, None, [0, 1, 2, 3, 4, 5, 3, 4, 5, 3, 4, 5]),
+        (
+            true,
+            0,
+            9,
+            Some((1, 2)),
+            [0, 1, 2, 3, 1, 5, 6, 1, 8, 9, 1, 11],
+        ),
+        (
+            false,
+            0,
+            1,
+            Some((2, 9)),
+            [0, 1, 2, 3, 4, 5, 6, 6, 8, 9, 9, 11],
+        ),
+        (
+            true,
+            0,
+            9,
+            Some((2, 1)),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        ),
+        (
+            false,
+            0,
+            9,
+            Some((9, 2)),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        ),
+    ] {
+        let mut value = value.clone();
+
+        MoshSmear {
+            line_size: 3,
+            channel_count: 1,
+            vertical,
+            source,
+            length,
+            span,
+        }
+        .glitch(&mut value);
+
+        assert_eq!(value, expected);
+    }
+
+    for orientation in [MoshOrientation::Horizontal, MoshOrientation::Vertical] {
+        let input = read_file("src/util/test-rgb-alpha.png").unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.pixelation = 1;
+        image.options.smear = 1.0;
+        image.options.smear_direction = orientation;
+        image.options.smear_partial = true;
+        image.mosh().unwrap();
+
+        let moshed = image.data.buf.clone();
+        let plan = image.plan().unwrap();
+
+        assert!(plan.chunks.iter().all(|chunk| chunk
+            .smear
+            .as_ref()
+            .is_some_and(|smear| smear.span.is_some())));
+        assert_ne!(moshed, image.data.image);
+    }
+}
+
+#[test]
+fn region() {
+    let value: Vec<u8> = (0..10).collect();
+
+    for (lines, columns, region, expected) in [
+        (
+            (0, 2),
+            None,
+            MoshRegion::Copy(3),
+            [0, 1, 2, 3, 4, 5, 0, 1, 2, 3],
+        ),
+        (
+            (1, 3),
+            None,
+            MoshRegion::Copy(2),
+            [0, 1, 2, 3, 2, 3, 4, 5, 8, 9],
+        ),
+        (
+            (0, 2),
+            None,
+            MoshRegion::Swap(3),
+            [6, 7, 8, 9, 4, 5, 0, 1, 2, 3],
+        ),
+        (
+            (0, 2),
+            None,
+            MoshRegion::Swap(1),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        ),
+        (
+            (1, 2),
+            Some((1, 2)),
+            MoshRegion::Tile(9),
+            [0, 1, 2, 3, 4, 3, 6, 3, 8, 3],
+        ),
+    ] {
+        let mut value = value.clone();
+        MoshData::relocate(&mut value, 2, lines, columns, &region);
+
+        assert_eq!(value, expected);
+    }
+
+    let input = read_file("src/util/test-rgb.png").unwrap();
+    let mut image = MoshCore::new();
+    image.read_image(&input).unwrap();
+
+    image.options.pixelation = 1;
+    image.options.chunk_swap = 1.0;
+    image.mosh().unwrap();
+
+    let plan = image.plan().unwrap();
+
+    for chunk in &plan.chunks {
+        if let Some(MoshRegion::Swap(line)) = chunk.region {
+            let size = chunk.last_line - chunk.first_line;
+            assert!(line + size <= chunk.first_line || line >= chunk.last_line);
+        }
+    }
+
+    assert!(plan.chunks.iter().any(|chunk| chunk.region.is_some()));
+}
+
+#[test]
+fn crush() {
+    let crushed = |value: &[u8], channel_count, depth, bits: &[u8], dither| {
+        let mut value = value.to_vec();
+
+        MoshCrush {
+            line_size: 4,
+            channel_count,
+            depth,
+            bits: bits.to_vec(),
+            dither,
+        }
+        .glitch(&mut value);
+
+        value
+    };
+
+    let gray = [128; 4];
+
+    assert_eq!(
+        crushed(&[0, 100, 130, 255], 1, 8, &[1], MoshDither::None),
+        [0, 0, 255, 255]
+    );
+    assert_eq!(
+        crushed(&gray, 1, 8, &[1], MoshDither::Ordered),
+        [0, 255, 0, 255]
+    );
+    assert_eq!(
+        crushed(&gray, 1, 8, &[1], MoshDither::Diffusion),
+        [255, 0, 255, 0]
+    );
+    assert_eq!(
+        crushed(&[100, 100, 200, 200], 2, 8, &[8, 1], MoshDither::None),
+        [100, 0, 200, 255]
+    );
+    assert_eq!(crushed(&[9; 4], 1, 4, &[2], MoshDither::None), [10; 4]);
+
+    let mut value: Vec<u16> = vec![0x8000, 0x1234];
+
+    MoshCrush {
+        line_size: 2,
+        channel_count: 1,
+        depth: 16,
+        bits: vec![1],
+        dither: MoshDither::None,
+    }
+    .glitch(&mut value);
+
+    assert_eq!(value, [65535, 0]);
+
+    for dither in [MoshDither::Ordered, MoshDither::Diffusion] {
+        let input = read_file("src/util/test-rgb-alpha.png").unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.crush = 1.0;
+        image.options.crush_bits = vec![2];
+        image.options.crush_dither = dither;
+        image.options.crush_global = true;
+        image.options.preserve_alpha = true;
+        image.mosh().unwrap();
+
+        let moshed = image.data.buf.clone();
+        let plan = image.plan().unwrap();
+        let last = plan.chunks.last().unwrap();
+
+        assert_eq!(last.last_line, image.data.height as usize);
+        assert!(last.crush.is_some());
+        assert_eq!(
+            plan.chunks
+                .iter()
+                .filter(|chunk| chunk.crush.is_some())
+                .count(),
+            1
+        );
+
+        let alpha = |buf: &[u8]| -> Vec<u8> { buf.iter().skip(3).step_by(4).copied().collect() };
+
+        assert_eq!(alpha(&moshed), alpha(&image.data.image));
+        assert_ne!(moshed, image.data.image);
+    }
+}
+
+#[test]
+fn color_space() {
+    let convert = |value: &[u8], space, decode| {
+        let mut value = value.to_vec();
+
+        MoshColor {
+            channel_count: 4,
+            space,
+            decode,
+        }
+        .glitch(&mut value);
+
+        value
+    };
+
+    let value = [255, 0, 0, 7, 0, 255, 0, 7, 30, 60, 90, 7];
+
+    for (space, expected) in [
+        (
+            MoshColorSpace::YCbCr,
+            [76, 84, 255, 7, 150, 43, 21, 7, 54, 148, 110, 7],
+        ),
+        (
+            MoshColorSpace::Hsv,
+            [0, 255, 255, 7, 85, 255, 255, 7, 149, 170, 90, 7],
+        ),
+    ] {
+        let encoded = convert(&value, space, false);
+        let decoded = convert(&encoded, space, true);
+
+        assert_eq!(encoded, expected);
+        assert!(decoded
+            .iter()
+            .zip(value)
+            .all(|(decoded, value)| decoded.abs_diff(value) <= 1));
+    }
+
+    assert_eq!(convert(&value, MoshColorSpace::Rgb, false), value);
+
+    let mut results = Vec::new();
+
+    for space in [
+        MoshColorSpace::Rgb,
+        MoshColorSpace::YCbCr,
+        MoshColorSpace::Hsv,
+    ] {
+        let input = read_file("src/util/test-rgb-alpha.png").unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.channel_swap = 1.0;
+        image.options.channel_shift = 1.0;
+        image.options.color_space = space;
+        image.options.preserve_alpha = true;
+        image.mosh().unwrap();
+
+        let moshed = image.data.buf.clone();
+        let plan = image.plan().unwrap();
+        let alpha = |buf: &[u8]| -> Vec<u8> { buf.iter().skip(3).step_by(4).copied().collect() };
+
+        assert!(plan
+            .chunks
+            .iter()
+            .all(|chunk| chunk.color.is_some() == (space != MoshColorSpace::Rgb)));
+        assert_eq!(alpha(&moshed), alpha(&image.data.image));
+        assert!(!results.contains(&moshed));
+
+        results.push(moshed);
+    }
+
+    // Other effects are left in RGB
+    for file in ["src/util/test-rgb.png", "src/util/test-rgb-16.png"] {
+        let input = read_file(file).unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.channel_swap = 0.0;
+        image.options.channel_shift = 0.0;
+        image.options.sort = 1.0;
+        image.mosh().unwrap();
+
+        let moshed = image.data.buf.clone();
+
+        for space in [MoshColorSpace::YCbCr, MoshColorSpace::Hsv] {
+            image.options.color_space = space;
+            image.mosh().unwrap();
+
+            assert!(image
+                .plan()
+                .unwrap()
+                .chunks
+                .iter()
+                .all(|chunk| chunk.color.is_none()));
+            assert_eq!(image.data.buf, moshed);
+        }
+    }
+}
+
+#[test]
+fn skew() {
+    let value: Vec<u8> = (0..12).collect();
+
+    for (skew, expected) in [
+        (1, [0, 1, 2, 3, 5, 6, 7, 8, 10, 11, 0, 1]),
+        (-1, [0, 1, 2, 3, 3, 4, 5, 6, 6, 7, 8, 9]),
+        (0, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+    ] {
+        let mut value = value.clone();
+
+        MoshSkew { line_size: 4, skew }.glitch(&mut value);
+
+        assert_eq!(value, expected);
+    }
+
+    for (direction, left) in [
+        (MoshSkewDirection::Left, true),
+        (MoshSkewDirection::Right, false),
+    ] {
+        let input = read_file("src/util/test-rgb.png").unwrap();
+        let mut image = MoshCore::new();
+        image.read_image(&input).unwrap();
+
+        image.options.skew = 1.0;
+        image.options.skew_direction = direction;
+        image.mosh().unwrap();
+
+        let moshed = image.data.buf.clone();
+        let plan = image.plan().unwrap();
+
+        assert!(plan.chunks.iter().all(|chunk| chunk
+            .skew
+            .as_ref()
+            .is_some_and(|skew| (skew.skew > 0) == left)));
+        assert_ne!(moshed, image.data.image);
+
+        let pipeline = MoshPipeline::from_options(&image.options);
+        image.mosh_with(&pipeline).unwrap();
+
+        assert_ne!(image.data.buf, image.data.image);
+    }
+}
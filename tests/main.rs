@@ -40,13 +40,142 @@ mod cli {
     }
 
     #[test]
-    fn unsupported_color_type() -> Result<(), Box<dyn Error>> {
+    fn invalid_parameters() -> Result<(), Box<dyn Error>> {
         let mut cmd = Command::cargo_bin("pixelmosh")?;
 
-        cmd.arg("src/util/test-indexed.png");
-        cmd.assert()
+        cmd.arg("src/util/test-grayscale.png")
+            .arg("--reverse")
+            .arg("2")
+            .assert()
+            .failure()
+            .stderr(contains("Invalid `reverse` value, allowed range is 0..=1"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_value() -> Result<(), Box<dyn Error>> {
+        Command::cargo_bin("pixelmosh")?
+            .arg("src/util/test-grayscale.png")
+            .arg("--color-space")
+            .arg("yuv")
+            .assert()
             .failure()
-            .stderr(contains("Unsupported color type"));
+            .stderr(contains("[possible values: rgb, ycbcr, hsv]"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rgb_split_offsets() -> Result<(), Box<dyn Error>> {
+        Command::cargo_bin("pixelmosh")?
+            .arg("src/util/test-grayscale.png")
+            .arg("--rgb-split-offsets")
+            .arg("-4:0,x")
+            .assert()
+            .failure()
+            .stderr(contains("Invalid `offsets` value"));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn recipe() -> Result<(), Box<dyn Error>> {
+        Command::cargo_bin("pixelmosh")?
+            .arg("src/util/test-rgb.png")
+            .arg("--seed")
+            .arg("1309999")
+            .arg("--save-recipe")
+            .arg("moshed-recipe.toml")
+            .arg("--output")
+            .arg("moshed-recipe-1")
+            .assert()
+            .success();
+
+        Command::cargo_bin("pixelmosh")?
+            .arg("src/util/test-rgb.png")
+            .arg("--recipe")
+            .arg("moshed-recipe.toml")
+            .arg("--output")
+            .arg("moshed-recipe-2")
+            .assert()
+            .success()
+            .stdout(contains("seed: \x1b[3m1309999"));
+
+        let output_1 = File::open("moshed-recipe-1.png")?;
+        let output_2 = File::open("moshed-recipe-2.png")?;
+
+        let mut file_1 = BufReader::new(output_1);
+        let checksum_1 = adler32(&mut file_1)?;
+
+        let mut file_2 = BufReader::new(output_2);
+        let checksum_2 = adler32(&mut file_2)?;
+
+        assert_eq!(checksum_1, checksum_2);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn recipe_batch() -> Result<(), Box<dyn Error>> {
+        Command::cargo_bin("pixelmosh")?
+            .arg("src/util/test-grayscale.png")
+            .arg("--seed")
+            .arg("1309999")
+            .arg("--save-recipe")
+            .arg("moshed-recipe-batch.toml")
+            .arg("--output")
+            .arg("moshed-recipe-batch")
+            .assert()
+            .success();
+
+        Command::cargo_bin("pixelmosh")?
+            .arg("src/util/test-grayscale.png")
+            .arg("--recipe")
+            .arg("moshed-recipe-batch.toml")
+            .arg("--batch")
+            .arg("2")
+            .arg("--output")
+            .arg("moshed-recipe-batch")
+            .assert()
+            .success();
+
+        let output_1 = File::open("moshed-recipe-batch-001.png")?;
+        let output_2 = File::open("moshed-recipe-batch-002.png")?;
+
+        let mut file_1 = BufReader::new(output_1);
+        let checksum_1 = adler32(&mut file_1)?;
+
+        let mut file_2 = BufReader::new(output_2);
+        let checksum_2 = adler32(&mut file_2)?;
+
+        assert_eq!(checksum_1, 3_406_465_027);
+        assert_eq!(checksum_2, 1_498_643_664);
+
+        Ok(())
+    }
+
+    #[test]
+    fn indexed() -> Result<(), Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("pixelmosh")?;
+
+        cmd.arg("src/util/test-indexed.png")
+            .arg("--indexed")
+            .arg("both")
+            .arg("--seed")
+            .arg("1309999")
+            .arg("--output")
+            .arg("moshed-indexed-test")
+            .assert()
+            .success();
+
+        let output = File::open("moshed-indexed-test.png")?;
+        let mut file = BufReader::new(output);
+        let checksum = adler32(&mut file)?;
+
+        assert_eq!(checksum, 2_366_854_093);
 
         Ok(())
     }